@@ -0,0 +1,38 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Writes the table of the files embedded into the binary, every file in assets/ is part of it.
+fn main() {
+    let assets = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("assets");
+    println!("cargo:rerun-if-changed={}", assets.display());
+    let mut files = Vec::new();
+    collect_files(&assets, &mut files);
+    files.sort();
+
+    let mut table = String::from("pub(crate) fn embedded(path: &str) -> Option<&'static [u8]> {\n    let bytes: &'static [u8] = match path {\n");
+    for file in files {
+        let name: Vec<String> = file
+            .strip_prefix(&assets)
+            .unwrap()
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        table.push_str(&format!("        {:?} => include_bytes!({:?}),\n", name.join("/"), file.display().to_string()));
+    }
+    table.push_str("        _ => return None,\n    };\n    Some(bytes)\n}\n");
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("embedded.rs");
+    fs::write(out, table).unwrap();
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
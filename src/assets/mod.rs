@@ -0,0 +1,97 @@
+pub(crate) mod watcher;
+
+use macroquad::prelude::*;
+use quad_snd::decoder;
+use quad_snd::mixer::Sound;
+use std::fmt;
+
+/// Directory the assets are read from at runtime, relative to the working directory.
+pub const ASSETS_DIR: &str = "assets";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetError {
+    pub path: String,
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not load asset '{}'", self.path)
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+/// Native builds prefer the file in `ASSETS_DIR` so it can be edited without recompiling,
+/// wasm builds prefer the copy embedded in the binary. Either way the other one is the fallback.
+pub async fn load_bytes(path: &str) -> Result<Vec<u8>, AssetError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        if let Ok(bytes) = load_file(&disk_path(path)).await {
+            return Ok(bytes);
+        }
+        embedded(path).map(|bytes| bytes.to_vec()).ok_or_else(|| AssetError {
+            path: path.to_string(),
+        })
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        if let Some(bytes) = embedded(path) {
+            return Ok(bytes.to_vec());
+        }
+        load_file(&disk_path(path)).await.map_err(|_| AssetError {
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Reads an asset from `ASSETS_DIR` right away, without the embedded fallback.
+pub fn read_from_disk(path: &str) -> Result<Vec<u8>, AssetError> {
+    std::fs::read(disk_path(path)).map_err(|_| AssetError {
+        path: path.to_string(),
+    })
+}
+
+pub async fn load_string(path: &str) -> Result<String, AssetError> {
+    let bytes = load_bytes(path).await?;
+    String::from_utf8(bytes).map_err(|_| AssetError {
+        path: path.to_string(),
+    })
+}
+
+pub async fn load_texture(path: &str) -> Result<Texture2D, AssetError> {
+    let bytes = load_bytes(path).await?;
+    Ok(texture_from_bytes(&bytes))
+}
+
+pub fn texture_from_bytes(bytes: &[u8]) -> Texture2D {
+    let image = Image::from_file_with_format(bytes, None);
+    let texture: Texture2D = Texture2D::from_image(&image);
+    texture.set_filter(FilterMode::Nearest);
+    texture
+}
+
+pub async fn load_font(path: &str) -> Result<Font, AssetError> {
+    let bytes = load_bytes(path).await?;
+    load_ttf_font_from_bytes(&bytes).map_err(|_| AssetError {
+        path: path.to_string(),
+    })
+}
+
+pub async fn load_sound(path: &str) -> Result<Sound, AssetError> {
+    let bytes = load_bytes(path).await?;
+    let sound = if path.ends_with(".ogg") {
+        decoder::read_ogg(&bytes).ok()
+    } else {
+        decoder::read_wav(&bytes).ok()
+    };
+    sound.ok_or_else(|| AssetError {
+        path: path.to_string(),
+    })
+}
+
+pub fn disk_path(path: &str) -> String {
+    format!("{}/{}", ASSETS_DIR, path)
+}
+
+// `embedded(path)`, the files of `ASSETS_DIR` built into the binary
+include!(concat!(env!("OUT_DIR"), "/embedded.rs"));
@@ -0,0 +1,40 @@
+use crate::assets::disk_path;
+use macroquad::prelude::get_time;
+use std::time::SystemTime;
+
+const POLL_INTERVAL: f64 = 0.5;
+
+/// Polls the modification time of assets on disk, used to hot-reload them.
+pub struct FileWatcher {
+    files: Vec<(String, Option<SystemTime>)>,
+    next_poll: f64,
+}
+
+impl FileWatcher {
+    pub fn new(paths: &[String]) -> FileWatcher {
+        FileWatcher {
+            files: paths.iter().map(|p| (disk_path(p), modified(&disk_path(p)))).collect(),
+            next_poll: get_time() + POLL_INTERVAL,
+        }
+    }
+
+    pub fn changed(&mut self) -> bool {
+        if cfg!(target_arch = "wasm32") || get_time() < self.next_poll {
+            return false;
+        }
+        self.next_poll = get_time() + POLL_INTERVAL;
+        let mut changed = false;
+        for (path, last_modified) in self.files.iter_mut() {
+            let now = modified(path);
+            if now.is_some() && now != *last_modified {
+                *last_modified = now;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,158 @@
+use crate::assets;
+use crate::assets::AssetError;
+use crate::entity::body::PlayerEvent;
+use crate::storage::settings::Settings;
+use macroquad::prelude::*;
+use quad_snd::mixer::{PlaybackStyle, Sound, SoundId, SoundMixer, Volume};
+use std::collections::HashMap;
+
+pub const TITLE_MUSIC: &str = "title";
+pub const END_MUSIC: &str = "end";
+pub const JUMP_SOUND: &str = "jump";
+pub const DEAD_SOUND: &str = "dead";
+pub const CHECKPOINT_SOUND: &str = "checkpoint";
+pub const DUCK_GROUND_SOUND: &str = "duck_ground";
+pub const ILLUSION_SOUND: &str = "illusion";
+
+/// Seconds one scene track takes to fade over to the next.
+pub const CROSSFADE: f32 = 1.5;
+
+const SOUNDS: [(&str, &str, Bus); 7] = [
+    (TITLE_MUSIC, "music/start.ogg", Bus::Music),
+    (END_MUSIC, "music/end.ogg", Bus::Music),
+    (JUMP_SOUND, "sfx/jump.wav", Bus::Sfx),
+    (DEAD_SOUND, "sfx/dead.wav", Bus::Sfx),
+    (CHECKPOINT_SOUND, "sfx/checkpoint.wav", Bus::Sfx),
+    (DUCK_GROUND_SOUND, "sfx/duck_ground.wav", Bus::Sfx),
+    (ILLUSION_SOUND, "sfx/illusion.wav", Bus::Sfx),
+];
+
+/// Which volume setting a sound follows, music loops and is faded between scenes.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Bus {
+    Music,
+    Sfx,
+}
+
+struct Track {
+    name: &'static str,
+    id: SoundId,
+    gain: f32,
+}
+
+/// Owns the one mixer of the game, scenes play sounds by name through it.
+pub struct Audio {
+    mixer: SoundMixer,
+    sounds: HashMap<&'static str, Sound>,
+    music: Option<Track>,
+    fading_out: Vec<Track>,
+    fade_time: f32,
+    music_volume: f32,
+    sfx_volume: f32,
+}
+
+impl Audio {
+    pub async fn init(settings: &Settings) -> Result<Audio, AssetError> {
+        let mut sounds = HashMap::with_capacity(SOUNDS.len());
+        for (name, path, bus) in SOUNDS.iter() {
+            let mut sound = assets::load_sound(path).await?;
+            if *bus == Bus::Music {
+                sound.playback_style = PlaybackStyle::Looped;
+            }
+            sounds.insert(*name, sound);
+        }
+        Ok(Audio {
+            mixer: SoundMixer::new(),
+            sounds,
+            music: None,
+            fading_out: vec![],
+            fade_time: CROSSFADE,
+            music_volume: settings.music_volume,
+            sfx_volume: settings.sfx_volume,
+        })
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.music_volume = settings.music_volume;
+        self.sfx_volume = settings.sfx_volume;
+    }
+
+    pub fn play_sfx(&mut self, name: &str) {
+        match self.sounds.get(name) {
+            Some(sound) => {
+                let id = self.mixer.play(sound.clone());
+                self.mixer.set_volume(id, Volume(self.sfx_volume));
+            }
+            None => error!("no sound named '{}'", name),
+        }
+    }
+
+    pub fn play_event(&mut self, event: PlayerEvent) {
+        if let Some(name) = event_sound(event) {
+            self.play_sfx(name);
+        }
+    }
+
+    /// Fades the current track out and `name` in over `fade` seconds, nothing happens if it already plays.
+    pub fn play_music(&mut self, name: &'static str, fade: f32) {
+        if matches!(&self.music, Some(track) if track.name == name) {
+            return;
+        }
+        let sound = match self.sounds.get(name) {
+            Some(sound) => sound.clone(),
+            None => {
+                error!("no music named '{}'", name);
+                return;
+            }
+        };
+        self.fade_time = fade;
+        if let Some(track) = self.music.take() {
+            self.fading_out.push(track);
+        }
+        let id = self.mixer.play(sound);
+        let gain = if fade > 0.0 { 0.0 } else { 1.0 };
+        self.mixer.set_volume(id, Volume(self.music_volume * gain));
+        self.music = Some(Track { name, id, gain });
+    }
+
+    pub fn stop_music(&mut self, fade: f32) {
+        self.fade_time = fade;
+        if let Some(track) = self.music.take() {
+            self.fading_out.push(track);
+        }
+    }
+
+    /// Advances the fades and feeds the mixer, called once per rendered frame.
+    pub fn frame(&mut self) {
+        let step = if self.fade_time > 0.0 {
+            get_frame_time() / self.fade_time
+        } else {
+            1.0
+        };
+        if let Some(track) = &mut self.music {
+            track.gain = (track.gain + step).min(1.0);
+            self.mixer.set_volume(track.id, Volume(self.music_volume * track.gain));
+        }
+        for track in self.fading_out.iter_mut() {
+            track.gain = (track.gain - step).max(0.0);
+            if track.gain > 0.0 {
+                self.mixer.set_volume(track.id, Volume(self.music_volume * track.gain));
+            } else {
+                self.mixer.stop(track.id);
+            }
+        }
+        self.fading_out.retain(|track| track.gain > 0.0);
+        self.mixer.frame();
+    }
+}
+
+fn event_sound(event: PlayerEvent) -> Option<&'static str> {
+    match event {
+        PlayerEvent::Jumped => Some(JUMP_SOUND),
+        PlayerEvent::DuckLanded => Some(DUCK_GROUND_SOUND),
+        PlayerEvent::Died => Some(DEAD_SOUND),
+        PlayerEvent::Checkpoint => Some(CHECKPOINT_SOUND),
+        PlayerEvent::RevealedIllusion => Some(ILLUSION_SOUND),
+        PlayerEvent::Landed | PlayerEvent::Won => None,
+    }
+}
@@ -0,0 +1,671 @@
+use crate::entity::collision;
+use crate::entity::physics::PlayerPhysics;
+use crate::input::{Action, Controls};
+use crate::scene::game::GameState;
+use crate::scene::illusion;
+use crate::scene::level::MapLayers;
+use crate::scene::tile_properties::TileProperties;
+use crate::tilemap::tile_animation::TileAnim;
+use crate::tilemap::Tilemap;
+use crate::utils::fixed_step::{TICK, TICK_RATE};
+use macroquad::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const DUCK_DISTANCE_FIX: f32 = 8.0;
+pub(crate) const RAY_HEAD: (f32, f32)= (4.0, 4.0);
+pub(crate) const RAY_FEET: (f32, f32)= (4.0, 12.0);
+/// Keeps the slide on very slippery tiles finite.
+const MIN_FRICTION: f32 = 0.1;
+const SPAWN_DELAY_TICKS: u32 = TICK_RATE;
+/// How long one-way platforms let the player through after dropping down from one.
+const DROP_THROUGH_TICKS: u32 = TICK_RATE / 4;
+const CHECKPOINT_CUE_TICKS: u32 = TICK_RATE * 6 / 10;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+enum State {
+    SLIDE,
+    IDLE,
+    RUN,
+    AIR,
+    KILL,
+    WIN,
+    STAND
+}
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+enum Facing {
+    Left,
+    Right,
+    Camera,
+}
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+enum JumpState {
+    BuildUp,
+    Not,
+    Up,
+    Down,
+}
+
+/// The actions the player reacts to, sampled once per rendered frame and read by every tick of that frame.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct PlayerInput {
+    pub left: bool,
+    pub right: bool,
+    pub jump: bool,
+    pub duck: bool,
+    /// Confirm or jump went down since the last tick, kept until a tick has seen it.
+    pub respawn: bool,
+}
+
+impl PlayerInput {
+    pub fn sample(&mut self, controls: &Controls) {
+        self.left = controls.is_down(Action::MoveLeft);
+        self.right = controls.is_down(Action::MoveRight);
+        self.jump = controls.is_down(Action::Jump);
+        self.duck = controls.is_down(Action::Duck);
+        self.respawn |= controls.is_pressed(Action::Confirm) || controls.is_pressed(Action::Jump);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum PlayerEvent {
+    Jumped,
+    Landed,
+    DuckLanded,
+    Died,
+    Checkpoint,
+    /// Passed through or landed on an illusion tile that was not revealed yet.
+    RevealedIllusion,
+    Won,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Copy)]
+pub enum AnimState {
+    RunLeft,
+    RunRight,
+    StandLeft,
+    StandRight,
+    RunDuckLeft,
+    RunDuckRight,
+    StandDuckLeft,
+    StandDuckRight,
+    AirUp,
+    AirUpDuck,
+    AirDown,
+    AirDownDuck,
+    AirUpLeft,
+    AirUpDuckLeft,
+    AirUpRight,
+    AirUpDuckRight,
+    AirDownLeft,
+    AirDownDuckLeft,
+    AirDownRight,
+    AirDownDuckRight,
+    Idle,
+    IdleDuck,
+    Dead
+}
+
+/// The simulated part of the player, it needs no window so it can run headless.
+pub struct PlayerBody {
+    duck_distance: f32,
+    moving_timer: usize,
+    break_timer: usize,
+    air_timer: usize,
+    jump_up_timer: usize,
+    jump_down_timer: usize,
+    direction: Vec2,
+    pub position: Vec2,
+    collide_color: Color,
+    need_reset: bool,
+    jump_timer: u32,
+    state: State,
+    jump_state: JumpState,
+    animation_state: AnimState,
+    facing: Facing,
+    animations: HashMap<AnimState, TileAnim>,
+    spawn_ticks: u32,
+    drop_ticks: u32,
+    coyote_ticks: u32,
+    jump_buffer_ticks: u32,
+    jump_held: bool,
+    checkpoint: Option<Vec2>,
+    checkpoint_cue_ticks: u32,
+    events: Vec<PlayerEvent>,
+    physics: PlayerPhysics,
+}
+
+impl Default for PlayerBody {
+    fn default() -> Self {
+        PlayerBody::new()
+    }
+}
+
+impl PlayerBody {
+    pub fn new() -> Self {
+        let animations = get_animations();
+        Self {
+            duck_distance: 0.0,
+            moving_timer: 0,
+            break_timer: 0,
+            air_timer: 0,
+            jump_up_timer: 0,
+            jump_down_timer: 0,
+            direction: Vec2::ZERO,
+            position: Vec2::ZERO,
+            collide_color: SKYBLUE,
+            need_reset: true,
+            jump_timer: 0,
+            state: State::STAND,
+            jump_state: JumpState::Not,
+            animation_state: AnimState::StandRight,
+            facing: Facing::Camera,
+            animations,
+            spawn_ticks: SPAWN_DELAY_TICKS,
+            drop_ticks: 0,
+            coyote_ticks: 0,
+            jump_buffer_ticks: 0,
+            jump_held: false,
+            checkpoint: None,
+            checkpoint_cue_ticks: 0,
+            events: Vec::new(),
+            physics: PlayerPhysics::default(),
+        }
+    }
+    pub fn update(&mut self, tilemap: &mut Tilemap, layers: &MapLayers, properties: &TileProperties, input: &PlayerInput) -> Option<GameState> {
+        let mut gamestate= None;
+
+        self.animations.get_mut(&self.animation_state).unwrap().advance_by(Duration::from_secs_f32(TICK));
+
+        self.collide_color = SKYBLUE;
+
+        let delta = TICK;
+        let mut new_x = self.position.x;
+        let mut new_y = self.position.y;
+
+        if self.state == State::KILL && input.respawn {
+            gamestate = Some(GameState::DEAD);
+        }
+        if self.state == State::WIN {
+            self.spawn_ticks = SPAWN_DELAY_TICKS;
+            gamestate = Some(GameState::WIN);
+        }
+        self.spawn_ticks = self.spawn_ticks.saturating_sub(1);
+        self.drop_ticks = self.drop_ticks.saturating_sub(1);
+        self.coyote_ticks = self.coyote_ticks.saturating_sub(1);
+        self.jump_buffer_ticks = self.jump_buffer_ticks.saturating_sub(1);
+        self.checkpoint_cue_ticks = self.checkpoint_cue_ticks.saturating_sub(1);
+        if input.jump && !self.jump_held {
+            self.jump_buffer_ticks = self.physics.jump_buffer_ticks;
+        }
+        self.jump_held = input.jump;
+
+        if self.spawn_ticks == 0 && self.state != State::KILL{
+            if input.left {
+                let distance = if self.state != State::AIR {
+                    self.physics.move_factor * self.physics.move_speed_curve[self.moving_timer] * delta
+                }else{
+                    (self.physics.move_factor * self.physics.move_speed_curve[self.moving_timer] * delta) / self.physics.air_control_divisor
+                };
+                let moved = collision::sweep(tilemap, layers.collision, properties, vec2(new_x, new_y), self.hitbox(), vec2(-distance, 0.0), false);
+                new_x = moved.position.x;
+                new_y = moved.position.y;
+                if !moved.on_wall() {
+                    self.facing = Facing::Left;
+                    if self.state != State::AIR {
+                        self.state = State::RUN;
+                    }
+                    self.direction = vec2(-1.0, 0.0);
+                    if self.moving_timer < self.physics.move_speed_curve.len() - 1 {
+                        self.moving_timer += 1;
+                    }
+                } else {
+                    self.direction = vec2(1.0, 0.0);
+                    self.break_timer = self.physics.break_speed_curve.len() - 3;
+                    self.collide_color = PINK;
+                }
+            } else if input.right {
+                let distance = if self.state != State::AIR {
+                    self.physics.move_factor * self.physics.move_speed_curve[self.moving_timer] * delta
+                }else{
+                    (self.physics.move_factor * self.physics.move_speed_curve[self.moving_timer] * delta) / self.physics.air_control_divisor
+                };
+                let moved = collision::sweep(tilemap, layers.collision, properties, vec2(new_x, new_y), self.hitbox(), vec2(distance, 0.0), false);
+                new_x = moved.position.x;
+                new_y = moved.position.y;
+                if !moved.on_wall() {
+                    self.facing = Facing::Right;
+                    if self.state != State::AIR{
+                        self.state = State::RUN;
+                    }
+                    self.direction = vec2(1.0, 0.0);
+                    if self.moving_timer < self.physics.move_speed_curve.len() - 1 {
+                        self.moving_timer += 1;
+                    }
+                } else {
+                    self.direction = vec2(-1.0, 0.0);
+                    self.break_timer = self.physics.break_speed_curve.len() - 3;
+                    self.collide_color = GOLD;
+                }
+            } else {
+                self.moving_timer = 0;
+                if self.jump_state == JumpState::Not { //todo whats this
+                    if self.state == State::RUN {
+                        self.break_timer = 0;
+                        self.state = State::SLIDE
+                    }
+                    if self.break_timer < self.physics.break_speed_curve.len() - 1 {
+                        let ground = collision::ground_points(self.position, self.hitbox());
+                        let friction = properties.friction_at(tilemap, layers.collision, ground[0]);
+                        let distance = (self.physics.move_factor + 2.0) * self.physics.break_speed_curve[self.break_timer] * delta / friction.max(MIN_FRICTION);
+                        let dx = if self.direction.x > 0.0 { distance } else { -distance };
+                        let slide = collision::sweep(tilemap, layers.collision, properties, vec2(new_x, new_y), self.hitbox(), vec2(dx, 0.0), false);
+                        new_x = slide.position.x;
+                        new_y = slide.position.y;
+                        self.break_timer += 1;
+                    } else {
+                        if self.state != State::IDLE{
+                            self.state = State::STAND;
+                        }
+                        self.direction = vec2(0.0, 0.0);
+                    }
+                }
+
+                // animation transition run-> stand
+                match self.animation_state {
+                    AnimState::RunLeft => {
+                        self.animations.get_mut(&self.animation_state).unwrap().repeating = false;
+                        if self.animations.get_mut(&self.animation_state).unwrap().finish() {
+                            self.animations.get_mut(&self.animation_state).unwrap().reset();
+                            self.state = State::STAND;
+                            self.facing = Facing::Left;
+                        }
+                    }
+                    AnimState::RunRight => {
+                        self.animations.get_mut(&self.animation_state).unwrap().repeating = false;
+                        if self.animations.get_mut(&self.animation_state).unwrap().finish() {
+                            self.animations.get_mut(&self.animation_state).unwrap().reset();
+                            self.state = State::STAND;
+                            self.facing = Facing::Right;
+                        }
+                    }
+                    _ => {}
+                }
+            };
+            // duck + jump on a one-way platform drops through it instead of jumping,
+            // neither the ledge nor the press may start a jump once the drop is over
+            if input.duck && input.jump && self.jump_state == JumpState::Not
+                && collision::on_one_way(tilemap, layers.collision, properties, vec2(new_x, new_y), self.hitbox()) {
+                self.drop_ticks = DROP_THROUGH_TICKS;
+                self.coyote_ticks = 0;
+                self.jump_buffer_ticks = 0;
+            }
+
+            // jump, also shortly after walking off a ledge or with a press shortly before landing
+            let grounded = self.jump_state == JumpState::Not || (self.jump_state == JumpState::Down && self.coyote_ticks > 0);
+            let start_jump = grounded && (input.jump || self.jump_buffer_ticks > 0);
+            if self.drop_ticks == 0 && (start_jump || (input.jump && self.jump_state == JumpState::BuildUp)) {
+                if self.jump_up_timer < self.physics.jump_up_curve.len() - 1 {
+                    if self.jump_state != JumpState::BuildUp {
+                        self.events.push(PlayerEvent::Jumped);
+                        self.jump_state = JumpState::BuildUp;
+                        self.state = State::AIR;
+                        self.jump_down_timer = 0;
+                        self.coyote_ticks = 0;
+                        self.jump_buffer_ticks = 0;
+                    }
+                    self.jump_up_timer += 1;
+                    let factor = if self.is_crouched(){self.physics.jump_up_factor / 2.0} else {self.physics.jump_up_factor};
+                    let y = factor * self.physics.jump_up_curve[self.jump_up_timer] * delta;
+                    let rise = collision::sweep(tilemap, layers.collision, properties, vec2(new_x, new_y), self.hitbox(), vec2(0.0, -y), false);
+                    new_y = rise.position.y;
+                    if rise.on_ceiling() {
+                        self.jump_state = JumpState::Up;
+                    }
+                } else {
+                    self.state = State::AIR;
+                    self.jump_state = JumpState::Up;
+                }
+            }
+
+            //stop jumping
+            if !input.jump && self.jump_state == JumpState::BuildUp {
+                self.state = State::AIR;
+                self.jump_state = JumpState::Up;
+            }
+
+            if self.jump_state == JumpState::Up {
+                if self.air_timer > self.physics.jump_up_curve.len() - 1 {
+                    self.air_timer = 0;
+                    self.jump_state = JumpState::Down;
+                } else {
+                    self.air_timer += 1;
+                }
+            }
+
+            // transition AIR
+            if self.jump_state == JumpState::Down || self.jump_state == JumpState::Not {
+                let y = self.physics.jump_down_factor * self.physics.jump_down_curve[self.jump_down_timer] * delta;
+                let x = 2.0 * self.direction.x * delta;
+                let mut fall = collision::sweep(tilemap, layers.collision, properties, vec2(new_x, new_y), self.hitbox(), vec2(0.0, y), self.drop_ticks > 0);
+                // stay on the ground when it falls away a little, e.g. running down a slope
+                if !fall.on_floor() && self.jump_state == JumpState::Not && self.drop_ticks == 0 {
+                    let snap = collision::sweep(tilemap, layers.collision, properties, vec2(new_x, new_y), self.hitbox(), vec2(0.0, collision::STEP_HEIGHT), false);
+                    if snap.on_floor() {
+                        fall = snap;
+                    }
+                }
+                new_y = fall.position.y;
+                if !fall.on_floor() {
+                    if self.jump_down_timer < self.physics.jump_down_curve.len() - 1 {
+                        self.jump_down_timer += 1;
+                    }
+                    // drift sideways only once the fall is known, sliding on the ground must not add to it
+                    let drift = collision::sweep(tilemap, layers.collision, properties, vec2(new_x, new_y), self.hitbox(), vec2(x, 0.0), false);
+                    new_x = drift.position.x;
+                    new_y = drift.position.y;
+                    self.jump_state = JumpState::Down;
+                    self.state = State::AIR
+                } else {
+                    if self.jump_state == JumpState::Down {
+                        self.events.push(if self.is_crouched() { PlayerEvent::DuckLanded } else { PlayerEvent::Landed });
+                    }
+                    self.jump_down_timer = 0;
+                    self.jump_up_timer = 0;
+                    self.coyote_ticks = self.physics.coyote_ticks;
+                    if self.facing == Facing::Camera {
+                        self.state = State::STAND;
+                    }else{
+                        self.state = State::SLIDE;
+                    }
+                    self.jump_state = JumpState::Not
+                }
+            }
+
+            if input.duck {
+                self.duck_distance = DUCK_DISTANCE_FIX;
+            }else{
+                if !collision::overlaps(tilemap, layers.collision, properties, vec2(new_x, new_y), collision::hitbox(0.0)) {
+                    self.duck_distance = 0.0;
+                }
+            }
+
+            self.position.x = new_x;
+            self.position.y = new_y;
+
+            let at_head = properties.logic(tilemap.get_id_at_position(layers.logic, self.position() + vec2(RAY_HEAD.0,RAY_HEAD.1 + self.duck_distance)));
+            let at_feet = properties.logic(tilemap.get_id_at_position(layers.logic, self.position() + Vec2::from(RAY_FEET)));
+
+            // kill logic
+            if matches!(at_head, Some(p) if p.deadly) || matches!(at_feet, Some(p) if p.deadly) {
+                self.events.push(PlayerEvent::Died);
+                self.state = State::KILL;
+            } else if matches!(at_feet, Some(p) if p.exit) {
+                if self.state != State::WIN {
+                    self.events.push(PlayerEvent::Won);
+                }
+                self.state = State::WIN;
+            }
+
+            // illusions give themselves away once the player walks through or stands on them
+            if self.state != State::KILL {
+                let mut touched = vec![self.position() + vec2(RAY_HEAD.0, RAY_HEAD.1 + self.duck_distance), self.position() + Vec2::from(RAY_FEET)];
+                if self.jump_state == JumpState::Not {
+                    touched.extend(collision::ground_points(self.position, self.hitbox()).iter());
+                }
+                let mut revealed = false;
+                for position in touched {
+                    revealed |= illusion::reveal(tilemap, layers.logic, properties, position);
+                }
+                if revealed {
+                    self.events.push(PlayerEvent::RevealedIllusion);
+                }
+            }
+
+            // checkpoints, the player respawns standing where it touched the last one
+            if self.state != State::KILL && (matches!(at_head, Some(p) if p.checkpoint) || matches!(at_feet, Some(p) if p.checkpoint)) {
+                let checkpoint = tilemap.snap_to_tile(self.position() + vec2(RAY_HEAD.0, RAY_HEAD.1));
+                if self.checkpoint != Some(checkpoint) {
+                    self.checkpoint = Some(checkpoint);
+                    self.checkpoint_cue_ticks = CHECKPOINT_CUE_TICKS;
+                    self.events.push(PlayerEvent::Checkpoint);
+                }
+            }
+        }
+        // setting animationstate
+        let old_animationstate = self.animation_state;
+        self.animation_state = self.get_animation_state();
+        if old_animationstate !=  self.animation_state{
+            self.animations.get_mut(&old_animationstate).unwrap().reset();
+            self.animations.get_mut(&old_animationstate).unwrap().repeating = false;
+            self.animations.get_mut(&self.animation_state).unwrap().repeating = true;
+        }
+        gamestate
+    }
+    pub fn position(&self) -> Vec2 {
+        if self.animation_state == AnimState::StandLeft || self.animation_state == AnimState::StandRight || self.animation_state == AnimState::Idle {
+            return self.position.round();
+        }
+        self.position.round()
+    }
+    pub fn reset(&mut self, tilemap: &Tilemap, layers: &MapLayers, properties: &TileProperties){
+        self.state = State::IDLE;
+        self.animation_state = AnimState::Idle;
+        self.jump_state = JumpState::Not;
+        self.jump_timer = 0;
+        self.jump_up_timer = 0;
+        self.jump_down_timer = 0;
+        self.air_timer = 0;
+        self.moving_timer = 0;
+        self.break_timer = self.physics.break_speed_curve.len();
+        self.duck_distance = 0.0;
+        self.direction = Vec2::ZERO;
+        self.need_reset = false;
+        self.spawn_ticks = SPAWN_DELAY_TICKS;
+        self.drop_ticks = 0;
+        self.coyote_ticks = 0;
+        self.jump_buffer_ticks = 0;
+        self.jump_held = false;
+        self.position = match self.checkpoint {
+            Some(checkpoint) => checkpoint,
+            None => properties.find(tilemap, layers.logic, |p| p.spawn)[0],
+        };
+        self.facing = Facing::Camera;
+        for (_, a) in self.animations.iter_mut() {
+            a.reset();
+        }
+    }
+    pub fn clear_checkpoint(&mut self) {
+        self.checkpoint = None;
+    }
+    pub fn checkpoint(&self) -> Option<Vec2> {
+        self.checkpoint
+    }
+    pub fn set_checkpoint(&mut self, checkpoint: Option<Vec2>) {
+        self.checkpoint = checkpoint;
+    }
+    /// How far the cue of a just reached checkpoint has spread, from 0 to 1.
+    pub fn checkpoint_cue(&self) -> Option<f32> {
+        if self.checkpoint_cue_ticks == 0 {
+            None
+        } else {
+            Some(1.0 - self.checkpoint_cue_ticks as f32 / CHECKPOINT_CUE_TICKS as f32)
+        }
+    }
+    pub fn physics(&self) -> &PlayerPhysics {
+        &self.physics
+    }
+    /// Swaps the movement tunables, the timers are kept inside the new curves.
+    pub fn set_physics(&mut self, physics: PlayerPhysics) {
+        self.moving_timer = self.moving_timer.min(physics.move_speed_curve.len() - 1);
+        self.break_timer = self.break_timer.min(physics.break_speed_curve.len());
+        self.jump_up_timer = self.jump_up_timer.min(physics.jump_up_curve.len() - 1);
+        self.jump_down_timer = self.jump_down_timer.min(physics.jump_down_curve.len() - 1);
+        self.physics = physics;
+    }
+    pub fn take_events(&mut self) -> Vec<PlayerEvent> {
+        std::mem::take(&mut self.events)
+    }
+    pub fn duck_distance(&self) -> f32 {
+        self.duck_distance
+    }
+    pub fn hitbox(&self) -> Rect {
+        collision::hitbox(self.duck_distance)
+    }
+    pub fn collide_color(&self) -> Color {
+        self.collide_color
+    }
+    pub fn animation_source(&self) -> Option<Rect> {
+        self.animations.get(&self.get_animation_state()).unwrap().source()
+    }
+    /// Whether the player can stay where it is on a (re)loaded map.
+    pub fn fits_on(&self, tilemap: &Tilemap, layers: &MapLayers, properties: &TileProperties) -> bool {
+        tilemap.is_inside_map(self.position)
+            && tilemap.is_inside_map(self.position + vec2(8.0, 16.0))
+            && !collision::overlaps(tilemap, layers.collision, properties, self.position, self.hitbox())
+    }
+    pub fn get_animation_state(&self) -> AnimState{
+        return match self.state {
+            State::SLIDE => {
+                if self.facing == Facing::Left {
+                    if self.is_crouched() {
+                        return AnimState::RunDuckLeft;
+                    }
+                    return AnimState::RunLeft;
+                }
+                if self.facing == Facing::Right {
+                    if self.is_crouched() {
+                        return AnimState::RunDuckRight;
+                    }
+                    return AnimState::RunRight;
+                }
+                if self.is_crouched() {
+                    return AnimState::IdleDuck;
+                }
+                AnimState::Idle
+            }
+            State::IDLE => {
+                if self.is_crouched() {
+                    return AnimState::IdleDuck;
+                }
+                AnimState::Idle
+            }
+            State::RUN => {
+                if self.facing == Facing::Left {
+                    if self.is_crouched() {
+                        return AnimState::RunDuckLeft;
+                    }
+                    return AnimState::RunLeft;
+                }
+                if self.is_crouched() {
+                    return AnimState::RunDuckRight;
+                }
+                AnimState::RunRight
+            }
+            State::KILL => {
+                AnimState::Dead
+            }
+            State::STAND => {
+                if self.facing == Facing::Left {
+                    if self.is_crouched() {
+                        return AnimState::StandDuckLeft;
+                    }
+                    return AnimState::StandLeft;
+                }
+                if self.facing == Facing::Right {
+                    if self.is_crouched() {
+                        return AnimState::StandDuckRight;
+                    }
+                    return AnimState::StandRight;
+                }
+                if self.is_crouched() {
+                    return AnimState::IdleDuck;
+                }
+                AnimState::Idle
+            }
+            State::AIR => {
+                if self.facing == Facing::Left {
+                    return if self.jump_state == JumpState::Up {
+                        if self.is_crouched() {
+                            return AnimState::AirUpDuckLeft;
+                        }
+                        AnimState::AirUpLeft
+                    } else { // down
+                        if self.is_crouched() {
+                            return AnimState::AirDownDuckLeft;
+                        }
+                        AnimState::AirDownLeft
+                    }
+                }
+                if self.facing == Facing::Right {
+                    return if self.jump_state == JumpState::Up {
+                        if self.is_crouched() {
+                            return AnimState::AirUpDuckRight;
+                        }
+                        AnimState::AirUpRight
+                    } else { // down
+                        if self.is_crouched() {
+                            return AnimState::AirDownDuckRight;
+                        }
+                        AnimState::AirDownRight
+                    }
+                }
+                return if self.jump_state == JumpState::Up {
+                    if self.is_crouched() {
+                        return AnimState::AirUpDuck;
+                    }
+                    AnimState::AirUp
+                } else { // down
+                    if self.is_crouched() {
+                        return AnimState::AirDownDuck;
+                    }
+                    AnimState::AirDown
+                }
+            }
+            State::WIN => {
+                return AnimState::Idle;
+            }
+        }
+    }
+    pub fn is_crouched(&self) -> bool{
+        self.duck_distance > 0.0
+    }
+}
+
+fn get_animations() -> HashMap<AnimState, TileAnim> {
+    let player_tilemap = Tilemap::new(Rect::new(0.0, 0.0, 64.0, 128.0), 8, 16, 8, 8);
+    let mut hashmap = HashMap::new();
+    hashmap.insert(AnimState::Idle, TileAnim::new(&player_tilemap, &[0, 1, 2], vec![Duration::from_millis(500), Duration::from_millis(200), Duration::from_millis(100)]));
+    hashmap.insert(AnimState::IdleDuck, TileAnim::new(&player_tilemap, &[4, 5, 6], vec![Duration::from_millis(500), Duration::from_millis(200), Duration::from_millis(100)]));
+
+    hashmap.insert(AnimState::StandRight, TileAnim::new(&player_tilemap, &[8, 9], vec![Duration::from_millis(500)]));
+    hashmap.insert(AnimState::StandDuckRight, TileAnim::new(&player_tilemap, &[12, 13], vec![Duration::from_millis(500)]));
+
+    hashmap.insert(AnimState::StandLeft, TileAnim::new(&player_tilemap, &[16, 17], vec![Duration::from_millis(500)]));
+    hashmap.insert(AnimState::StandDuckLeft, TileAnim::new(&player_tilemap, &[20, 48], vec![Duration::from_millis(500)]));
+
+    hashmap.insert(AnimState::RunRight, TileAnim::new(&player_tilemap, &[24, 25], vec![Duration::from_millis(80)]), );
+    hashmap.insert(AnimState::RunDuckRight, TileAnim::new(&player_tilemap, &[28, 29], vec![Duration::from_millis(80)]), );
+
+    hashmap.insert(AnimState::RunLeft, TileAnim::new(&player_tilemap, &[32, 33], vec![Duration::from_millis(80)]), );
+    hashmap.insert(AnimState::RunDuckLeft, TileAnim::new(&player_tilemap, &[36, 37], vec![Duration::from_millis(80)]), );
+
+    hashmap.insert(AnimState::AirUpRight, TileAnim::new(&player_tilemap, &[40, 40], vec![Duration::from_millis(80)]), );
+    hashmap.insert(AnimState::AirUpDuckRight, TileAnim::new(&player_tilemap, &[42, 42], vec![Duration::from_millis(80)]), );
+    hashmap.insert(AnimState::AirDownRight, TileAnim::new(&player_tilemap, &[44, 44], vec![Duration::from_millis(80)]), );
+    hashmap.insert(AnimState::AirDownDuckRight, TileAnim::new(&player_tilemap, &[46, 46], vec![Duration::from_millis(80)]), );
+
+    hashmap.insert(AnimState::AirUpLeft, TileAnim::new(&player_tilemap, &[48, 48], vec![Duration::from_millis(80)]), );
+    hashmap.insert(AnimState::AirUpDuckLeft, TileAnim::new(&player_tilemap, &[50, 50], vec![Duration::from_millis(80)]), );
+    hashmap.insert(AnimState::AirDownLeft, TileAnim::new(&player_tilemap, &[52, 52], vec![Duration::from_millis(80)]), );
+    hashmap.insert(AnimState::AirDownDuckLeft, TileAnim::new(&player_tilemap, &[54, 54], vec![Duration::from_millis(80)]), );
+
+    hashmap.insert(AnimState::AirUp, TileAnim::new(&player_tilemap, &[56, 56], vec![Duration::from_millis(80)]), );
+    hashmap.insert(AnimState::AirUpDuck, TileAnim::new(&player_tilemap, &[58, 58], vec![Duration::from_millis(80)]), );
+    hashmap.insert(AnimState::AirDown, TileAnim::new(&player_tilemap, &[60, 60], vec![Duration::from_millis(80)]), );
+    hashmap.insert(AnimState::AirDownDuck, TileAnim::new(&player_tilemap, &[62, 62], vec![Duration::from_millis(80)]), );
+
+    hashmap.insert(AnimState::Dead, TileAnim::new(&player_tilemap, &[63, 63], vec![Duration::from_millis(500)]));
+
+    hashmap
+}
@@ -0,0 +1,242 @@
+use crate::scene::tile_properties::{TileProperties, TileShape};
+use crate::tilemap::{LayerId, Tilemap};
+use macroquad::prelude::*;
+
+/// Hitbox of the standing player relative to its position, the sprite itself is 8x16.
+const HITBOX: Rect = Rect {
+    x: 1.0,
+    y: 3.0,
+    w: 7.0,
+    h: 13.0,
+};
+/// Edges that only touch a tile do not overlap it.
+const EPSILON: f32 = 0.001;
+/// Ground at most this far above the feet is stepped onto instead of blocking, e.g. half tiles.
+pub const STEP_HEIGHT: f32 = 4.0;
+
+/// Hitbox relative to the player position, crouching lowers its top by `duck_distance`.
+pub fn hitbox(duck_distance: f32) -> Rect {
+    Rect::new(HITBOX.x, HITBOX.y + duck_distance, HITBOX.w, HITBOX.h - duck_distance)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sweep {
+    pub position: Vec2,
+    pub normals: Vec<Vec2>,
+}
+
+impl Sweep {
+    pub fn on_floor(&self) -> bool {
+        self.normals.iter().any(|normal| normal.y < 0.0)
+    }
+    pub fn on_ceiling(&self) -> bool {
+        self.normals.iter().any(|normal| normal.y > 0.0)
+    }
+    pub fn on_wall(&self) -> bool {
+        self.normals.iter().any(|normal| normal.y == 0.0)
+    }
+}
+
+/// Moves `hitbox` at `position` by `delta`, first along x and then along y, and stops it flush
+/// against the solid tiles of the `collision` layer.
+/// Moving sideways steps up onto ground up to `STEP_HEIGHT` above the feet, which is how slopes are walked up.
+/// Shaped tiles block with their filled part from every side, one-way tiles only stop a downward move
+/// that starts above them and not even that if `drop_through` is set.
+pub fn sweep(tilemap: &Tilemap, collision: LayerId, properties: &TileProperties, position: Vec2, hitbox: Rect, delta: Vec2, drop_through: bool) -> Sweep {
+    let solid = Blocker { tilemap, collision, properties, one_way: false };
+    let mut sweep = Sweep { position, normals: vec![] };
+    if delta.x != 0.0 {
+        let (x, normal) = sweep_x(&solid, sweep.position, hitbox, delta.x);
+        sweep.position.x = x;
+        sweep.position.y = step_up(&solid, sweep.position, hitbox);
+        sweep.normals.extend(normal);
+    }
+    if delta.y != 0.0 {
+        let blocker = Blocker { one_way: delta.y > 0.0 && !drop_through, ..solid };
+        let (mut y, mut normal) = sweep_y(&blocker, sweep.position, hitbox, delta.y);
+        if delta.y > 0.0 {
+            let feet = sweep.position.y + hitbox.y + hitbox.h;
+            let landed = y + hitbox.y + hitbox.h;
+            if let Some((surface, surface_normal)) = ground(&solid, sweep.position, hitbox, feet, landed) {
+                if surface < landed - EPSILON || normal.is_none() {
+                    y = surface - hitbox.y - hitbox.h;
+                    normal = Some(surface_normal);
+                }
+            }
+        }
+        sweep.position.y = y;
+        sweep.normals.extend(normal);
+    }
+    sweep
+}
+
+/// Whether `hitbox` at `position` overlaps a solid tile, one-way tiles never count.
+/// Shaped tiles are measured below the middle of the hitbox like the ground is.
+pub fn overlaps(tilemap: &Tilemap, collision: LayerId, properties: &TileProperties, position: Vec2, hitbox: Rect) -> bool {
+    let solid = Blocker { tilemap, collision, properties, one_way: false };
+    solid.overlaps(position, hitbox)
+}
+
+pub fn on_one_way(tilemap: &Tilemap, collision: LayerId, properties: &TileProperties, position: Vec2, hitbox: Rect) -> bool {
+    let below = ground_points(position, hitbox);
+    below.iter().any(|point| properties.one_way_at(tilemap, collision, *point))
+        && !below.iter().any(|point| properties.solid_at(tilemap, collision, *point))
+}
+
+pub fn ground_points(position: Vec2, hitbox: Rect) -> [Vec2; 2] {
+    let bottom = position.y + hitbox.y + hitbox.h;
+    [
+        vec2(position.x + hitbox.x, bottom),
+        vec2(position.x + hitbox.x + hitbox.w - EPSILON, bottom),
+    ]
+}
+
+#[derive(Clone, Copy)]
+struct Blocker<'a> {
+    tilemap: &'a Tilemap,
+    collision: LayerId,
+    properties: &'a TileProperties,
+    one_way: bool,
+}
+
+impl Blocker<'_> {
+    fn center(&self, column: i32, row: i32) -> Option<Vec2> {
+        if column < 0 || row < 0 {
+            return None;
+        }
+        let center = (vec2(column as f32, row as f32) + vec2(0.5, 0.5)) * self.tilemap.tile_size();
+        if self.tilemap.is_inside_map(center) {
+            Some(center)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the tile at `column`, `row` is solid all over, full tiles and one-way ones if `one_way` is set.
+    fn blocks(&self, column: i32, row: i32) -> bool {
+        match self.center(column, row) {
+            Some(center) => {
+                self.properties.shape_at(self.tilemap, self.collision, center) == Some(TileShape::Full)
+                    || (self.one_way && self.properties.one_way_at(self.tilemap, self.collision, center))
+            }
+            None => false,
+        }
+    }
+
+    fn overlaps(&self, position: Vec2, hitbox: Rect) -> bool {
+        let size = self.tilemap.tile_size();
+        let (left, top) = (position.x + hitbox.x, position.y + hitbox.y);
+        let middle = left + hitbox.w / 2.0;
+        let columns = cell(left, size.x)..=cell(left + hitbox.w - EPSILON, size.x);
+        let rows = cell(top, size.y)..=cell(top + hitbox.h - EPSILON, size.y);
+        columns.into_iter().any(|column| {
+            rows.clone().any(|row| self.blocks(column, row) || self.fills(column, row, middle, top, top + hitbox.h))
+        })
+    }
+
+    /// Whether the filled part of the solid tile at `column`, `row` reaches between the heights `top` and `bottom`
+    /// at `x`, which is kept inside the tile.
+    fn fills(&self, column: i32, row: i32, x: f32, top: f32, bottom: f32) -> bool {
+        let size = self.tilemap.tile_size();
+        let x = x.clamp(column as f32 * size.x, (column + 1) as f32 * size.x);
+        match self.surface(column, row, x) {
+            Some((surface, _)) => surface < bottom - EPSILON && (row + 1) as f32 * size.y > top + EPSILON,
+            None => false,
+        }
+    }
+
+    /// Height and normal of the surface of the solid tile at `column`, `row`, below a hitbox centered at `x`.
+    fn surface(&self, column: i32, row: i32, x: f32) -> Option<(f32, Vec2)> {
+        let shape = self.properties.shape_at(self.tilemap, self.collision, self.center(column, row)?)?;
+        let size = self.tilemap.tile_size();
+        let height = shape.height_at((x - column as f32 * size.x) / size.x) * size.y;
+        let normal = vec2(-shape.rise() * size.y, -size.x).normalize();
+        Some(((row + 1) as f32 * size.y - height, normal))
+    }
+}
+
+/// The highest surface below the hitbox at `position` that lies between the heights `from` and `to`.
+/// Slopes are measured below the middle of the hitbox, so it sinks into them like the sprite does.
+fn ground(blocker: &Blocker, position: Vec2, hitbox: Rect, from: f32, to: f32) -> Option<(f32, Vec2)> {
+    let size = blocker.tilemap.tile_size();
+    let left = position.x + hitbox.x;
+    let middle = left + hitbox.w / 2.0;
+    let mut ground = None;
+    for column in cell(left, size.x)..=cell(left + hitbox.w - EPSILON, size.x) {
+        for row in cell(from - EPSILON, size.y)..=cell(to + EPSILON, size.y) {
+            if let Some((y, normal)) = blocker.surface(column, row, middle) {
+                let higher = !matches!(ground, Some((highest, _)) if highest <= y);
+                if y >= from - EPSILON && y <= to + EPSILON && higher {
+                    ground = Some((y, normal));
+                }
+            }
+        }
+    }
+    ground
+}
+
+/// Lifts the hitbox at `position` onto ground it sank into by at most `STEP_HEIGHT`, returns the new y.
+fn step_up(blocker: &Blocker, position: Vec2, hitbox: Rect) -> f32 {
+    let feet = position.y + hitbox.y + hitbox.h;
+    if let Some((surface, _)) = ground(blocker, position, hitbox, feet - STEP_HEIGHT, feet) {
+        let lifted = vec2(position.x, surface - hitbox.y - hitbox.h);
+        if !blocker.overlaps(lifted, hitbox) {
+            return lifted.y;
+        }
+    }
+    position.y
+}
+
+/// Only tiles in front of the leading edge are checked, the box may already overlap the ones it starts in.
+/// Tiles are measured at the side the box enters them from, what ends within `STEP_HEIGHT` above the feet
+/// is left to `step_up`.
+fn sweep_x(blocker: &Blocker, position: Vec2, hitbox: Rect, dx: f32) -> (f32, Option<Vec2>) {
+    let size = blocker.tilemap.tile_size();
+    let top = position.y + hitbox.y;
+    let step = top + hitbox.h - STEP_HEIGHT;
+    let rows = cell(top, size.y)..=cell(step - EPSILON, size.y);
+    if dx > 0.0 {
+        let edge = position.x + hitbox.x + hitbox.w;
+        for column in cell(edge - EPSILON, size.x) + 1..=cell(edge + dx - EPSILON, size.x) {
+            if rows.clone().any(|row| blocker.fills(column, row, column as f32 * size.x, top, step)) {
+                return (column as f32 * size.x - hitbox.x - hitbox.w, Some(vec2(-1.0, 0.0)));
+            }
+        }
+    } else {
+        let edge = position.x + hitbox.x;
+        for column in (cell(edge + dx, size.x)..cell(edge + EPSILON, size.x)).rev() {
+            if rows.clone().any(|row| blocker.fills(column, row, (column + 1) as f32 * size.x, top, step)) {
+                return ((column + 1) as f32 * size.x - hitbox.x, Some(vec2(1.0, 0.0)));
+            }
+        }
+    }
+    (position.x + dx, None)
+}
+
+fn sweep_y(blocker: &Blocker, position: Vec2, hitbox: Rect, dy: f32) -> (f32, Option<Vec2>) {
+    let size = blocker.tilemap.tile_size();
+    let left = position.x + hitbox.x;
+    let middle = left + hitbox.w / 2.0;
+    let columns = cell(left, size.x)..=cell(left + hitbox.w - EPSILON, size.x);
+    if dy > 0.0 {
+        let edge = position.y + hitbox.y + hitbox.h;
+        for row in cell(edge - EPSILON, size.y) + 1..=cell(edge + dy - EPSILON, size.y) {
+            if columns.clone().any(|column| blocker.blocks(column, row)) {
+                return (row as f32 * size.y - hitbox.y - hitbox.h, Some(vec2(0.0, -1.0)));
+            }
+        }
+    } else {
+        // every solid tile is filled along its bottom edge
+        let edge = position.y + hitbox.y;
+        for row in (cell(edge + dy, size.y)..cell(edge + EPSILON, size.y)).rev() {
+            if columns.clone().any(|column| blocker.surface(column, row, middle).is_some()) {
+                return ((row + 1) as f32 * size.y - hitbox.y, Some(vec2(0.0, 1.0)));
+            }
+        }
+    }
+    (position.y + dy, None)
+}
+
+fn cell(coordinate: f32, size: f32) -> i32 {
+    (coordinate / size).floor() as i32
+}
@@ -1,2 +1,4 @@
-//pub(crate) mod player_map;
+pub mod body;
+pub mod collision;
+pub mod physics;
 pub(crate) mod player;
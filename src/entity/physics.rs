@@ -0,0 +1,161 @@
+use nanoserde::DeJson;
+
+pub const PHYSICS_FILE: &str = "physics.json";
+/// The timers step through a curve and the break timer starts three entries before its end.
+const MIN_CURVE_LEN: usize = 3;
+/// FNV-1a, the hashers of std may change between releases and replays outlive those.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// The curves hold one speed per tick, the timers of the body walk along them.
+#[derive(Clone, Debug, PartialEq, DeJson)]
+pub struct PlayerPhysics {
+    pub move_factor: f32,
+    pub move_speed_curve: Vec<f32>,
+    pub break_speed_curve: Vec<f32>,
+    /// Moving sideways in the air is this many times slower than on the ground.
+    pub air_control_divisor: f32,
+    pub jump_up_factor: f32,
+    pub jump_up_curve: Vec<f32>,
+    pub jump_down_factor: f32,
+    pub jump_down_curve: Vec<f32>,
+    pub coyote_ticks: u32,
+    pub jump_buffer_ticks: u32,
+}
+
+impl Default for PlayerPhysics {
+    fn default() -> Self {
+        PlayerPhysics {
+            move_factor: 3.0,
+            move_speed_curve: vec![1.0, 1.0, 2.0, 3.0, 5.0, 8.0, 13.0, 21.0],
+            break_speed_curve: vec![21.0, 13.0, 8.0, 5.0, 3.0, 2.0, 1.0, 1.0],
+            air_control_divisor: 1.5,
+            jump_up_factor: 14.0,
+            jump_up_curve: vec![8.0, 16.0, 13.0, 10.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0, 1.0],
+            jump_down_factor: 12.0,
+            jump_down_curve: vec![1.0, 1.0, 2.0, 3.0, 8.0, 10.0],
+            coyote_ticks: 6,
+            jump_buffer_ticks: 6,
+        }
+    }
+}
+
+/// The tunables a level changes, everything it leaves out keeps the value of `physics.json`.
+#[derive(Clone, Debug, Default, PartialEq, DeJson)]
+pub struct PhysicsOverride {
+    #[nserde(default)]
+    pub move_factor: Option<f32>,
+    #[nserde(default)]
+    pub move_speed_curve: Option<Vec<f32>>,
+    #[nserde(default)]
+    pub break_speed_curve: Option<Vec<f32>>,
+    #[nserde(default)]
+    pub air_control_divisor: Option<f32>,
+    #[nserde(default)]
+    pub jump_up_factor: Option<f32>,
+    #[nserde(default)]
+    pub jump_up_curve: Option<Vec<f32>>,
+    #[nserde(default)]
+    pub jump_down_factor: Option<f32>,
+    #[nserde(default)]
+    pub jump_down_curve: Option<Vec<f32>>,
+    #[nserde(default)]
+    pub coyote_ticks: Option<u32>,
+    #[nserde(default)]
+    pub jump_buffer_ticks: Option<u32>,
+}
+
+impl PhysicsOverride {
+    pub fn from_json(json: &str) -> Result<PhysicsOverride, String> {
+        DeJson::deserialize_json(json).map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl PlayerPhysics {
+    pub fn from_json(json: &str) -> Result<PlayerPhysics, String> {
+        let physics: PlayerPhysics = DeJson::deserialize_json(json).map_err(|e| format!("{:?}", e))?;
+        physics.check()?;
+        Ok(physics)
+    }
+
+    pub fn with(&self, level: &PhysicsOverride) -> Result<PlayerPhysics, String> {
+        let physics = PlayerPhysics {
+            move_factor: level.move_factor.unwrap_or(self.move_factor),
+            move_speed_curve: level.move_speed_curve.clone().unwrap_or_else(|| self.move_speed_curve.clone()),
+            break_speed_curve: level.break_speed_curve.clone().unwrap_or_else(|| self.break_speed_curve.clone()),
+            air_control_divisor: level.air_control_divisor.unwrap_or(self.air_control_divisor),
+            jump_up_factor: level.jump_up_factor.unwrap_or(self.jump_up_factor),
+            jump_up_curve: level.jump_up_curve.clone().unwrap_or_else(|| self.jump_up_curve.clone()),
+            jump_down_factor: level.jump_down_factor.unwrap_or(self.jump_down_factor),
+            jump_down_curve: level.jump_down_curve.clone().unwrap_or_else(|| self.jump_down_curve.clone()),
+            coyote_ticks: level.coyote_ticks.unwrap_or(self.coyote_ticks),
+            jump_buffer_ticks: level.jump_buffer_ticks.unwrap_or(self.jump_buffer_ticks),
+        };
+        physics.check()?;
+        Ok(physics)
+    }
+
+    pub fn fingerprint(&self) -> u64 {
+        let PlayerPhysics {
+            move_factor,
+            move_speed_curve,
+            break_speed_curve,
+            air_control_divisor,
+            jump_up_factor,
+            jump_up_curve,
+            jump_down_factor,
+            jump_down_curve,
+            coyote_ticks,
+            jump_buffer_ticks,
+        } = self;
+        let mut words = vec![];
+        for curve in [move_speed_curve, break_speed_curve, jump_up_curve, jump_down_curve].iter() {
+            words.push(curve.len() as u32);
+            words.extend(curve.iter().map(|speed| speed.to_bits()));
+        }
+        words.extend([move_factor, air_control_divisor, jump_up_factor, jump_down_factor].iter().map(|factor| factor.to_bits()));
+        words.extend([*coyote_ticks, *jump_buffer_ticks].iter());
+        let mut hash = FNV_OFFSET;
+        for word in words {
+            for byte in word.to_le_bytes().iter() {
+                hash = (hash ^ *byte as u64).wrapping_mul(FNV_PRIME);
+            }
+        }
+        hash
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let curves = [
+            ("move_speed_curve", &self.move_speed_curve),
+            ("break_speed_curve", &self.break_speed_curve),
+            ("jump_up_curve", &self.jump_up_curve),
+            ("jump_down_curve", &self.jump_down_curve),
+        ];
+        for (name, curve) in curves.iter() {
+            if curve.len() < MIN_CURVE_LEN {
+                return Err(format!("{} needs at least {} entries", name, MIN_CURVE_LEN));
+            }
+            if let Some(speed) = curve.iter().find(|speed| !is_speed(**speed)) {
+                return Err(format!("{} holds {}, speeds must be finite and not negative", name, speed));
+            }
+        }
+        let factors = [
+            ("move_factor", self.move_factor),
+            ("jump_up_factor", self.jump_up_factor),
+            ("jump_down_factor", self.jump_down_factor),
+        ];
+        for (name, factor) in factors.iter() {
+            if !is_speed(*factor) {
+                return Err(format!("{} is {}, it must be finite and not negative", name, factor));
+            }
+        }
+        if !(self.air_control_divisor.is_finite() && self.air_control_divisor > 0.0) {
+            return Err("air_control_divisor must be finite and above 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn is_speed(value: f32) -> bool {
+    value.is_finite() && value >= 0.0
+}
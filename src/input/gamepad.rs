@@ -0,0 +1,120 @@
+use crate::input::Action;
+#[cfg(not(target_arch = "wasm32"))]
+use gilrs::{Axis, Button, EventType, Gamepad, Gilrs};
+#[cfg(not(target_arch = "wasm32"))]
+use macroquad::prelude::*;
+
+/// Stick deflection below this is treated as the stick resting in the middle.
+#[cfg(not(target_arch = "wasm32"))]
+const DEAD_ZONE: f32 = 0.3;
+
+/// Button and stick state of all connected gamepads, translated to actions.
+/// D-pad and left stick move (up and down work as jump and duck, which is what menus navigate with),
+/// south jumps and confirms, west ducks, east goes back and start pauses and confirms.
+/// Gamepads are only read on native builds.
+pub struct GamepadInput {
+    #[cfg(not(target_arch = "wasm32"))]
+    gilrs: Option<Gilrs>,
+    down: [bool; Action::ALL.len()],
+    pressed: [bool; Action::ALL.len()],
+}
+
+impl GamepadInput {
+    pub fn new() -> GamepadInput {
+        GamepadInput {
+            #[cfg(not(target_arch = "wasm32"))]
+            gilrs: match Gilrs::new() {
+                Ok(gilrs) => Some(gilrs),
+                Err(e) => {
+                    error!("gamepads are not available: {}", e);
+                    None
+                }
+            },
+            down: [false; Action::ALL.len()],
+            pressed: [false; Action::ALL.len()],
+        }
+    }
+
+    pub fn update(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let gilrs = match self.gilrs.as_mut() {
+                Some(gilrs) => gilrs,
+                None => return,
+            };
+            let was_down = self.down;
+            self.down = [false; Action::ALL.len()];
+            self.pressed = [false; Action::ALL.len()];
+            // buttons tapped within one frame only show up as events
+            while let Some(event) = gilrs.next_event() {
+                if let EventType::ButtonPressed(button, _) = event.event {
+                    for action in button_actions(button) {
+                        self.pressed[action_index(*action)] = true;
+                    }
+                }
+            }
+            for (_, gamepad) in gilrs.gamepads() {
+                for action in Action::ALL.iter() {
+                    self.down[action_index(*action)] |= is_action_down(&gamepad, *action);
+                }
+            }
+            for ((pressed, down), was_down) in self.pressed.iter_mut().zip(&self.down).zip(&was_down) {
+                *pressed |= *down && !*was_down;
+            }
+        }
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        self.down[action_index(action)]
+    }
+
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.pressed[action_index(action)]
+    }
+}
+
+fn action_index(action: Action) -> usize {
+    Action::ALL.iter().position(|a| *a == action).unwrap()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn button_actions(button: Button) -> &'static [Action] {
+    match button {
+        Button::South => &[Action::Jump, Action::Confirm],
+        Button::West => &[Action::Duck],
+        Button::East => &[Action::Back],
+        Button::Start => &[Action::Pause, Action::Confirm],
+        Button::DPadLeft => &[Action::MoveLeft],
+        Button::DPadRight => &[Action::MoveRight],
+        Button::DPadUp => &[Action::Jump],
+        Button::DPadDown => &[Action::Duck],
+        _ => &[],
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn is_action_down(gamepad: &Gamepad, action: Action) -> bool {
+    let buttons = [
+        Button::South,
+        Button::West,
+        Button::East,
+        Button::Start,
+        Button::DPadLeft,
+        Button::DPadRight,
+        Button::DPadUp,
+        Button::DPadDown,
+    ];
+    let button_down = buttons
+        .iter()
+        .any(|button| gamepad.is_pressed(*button) && button_actions(*button).contains(&action));
+    // the stick y axis points up
+    let stick = vec2(gamepad.value(Axis::LeftStickX), gamepad.value(Axis::LeftStickY));
+    let stick_down = match action {
+        Action::MoveLeft => stick.x < -DEAD_ZONE,
+        Action::MoveRight => stick.x > DEAD_ZONE,
+        Action::Jump => stick.y > DEAD_ZONE * 2.0,
+        Action::Duck => stick.y < -DEAD_ZONE * 2.0,
+        _ => false,
+    };
+    button_down || stick_down
+}
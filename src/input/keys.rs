@@ -0,0 +1,103 @@
+use macroquad::prelude::KeyCode;
+
+const KEY_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::Space, "Space"),
+    (KeyCode::Apostrophe, "'"),
+    (KeyCode::Comma, ","),
+    (KeyCode::Minus, "-"),
+    (KeyCode::Period, "."),
+    (KeyCode::Slash, "/"),
+    (KeyCode::Key0, "0"),
+    (KeyCode::Key1, "1"),
+    (KeyCode::Key2, "2"),
+    (KeyCode::Key3, "3"),
+    (KeyCode::Key4, "4"),
+    (KeyCode::Key5, "5"),
+    (KeyCode::Key6, "6"),
+    (KeyCode::Key7, "7"),
+    (KeyCode::Key8, "8"),
+    (KeyCode::Key9, "9"),
+    (KeyCode::Semicolon, ";"),
+    (KeyCode::Equal, "="),
+    (KeyCode::A, "A"),
+    (KeyCode::B, "B"),
+    (KeyCode::C, "C"),
+    (KeyCode::D, "D"),
+    (KeyCode::E, "E"),
+    (KeyCode::F, "F"),
+    (KeyCode::G, "G"),
+    (KeyCode::H, "H"),
+    (KeyCode::I, "I"),
+    (KeyCode::J, "J"),
+    (KeyCode::K, "K"),
+    (KeyCode::L, "L"),
+    (KeyCode::M, "M"),
+    (KeyCode::N, "N"),
+    (KeyCode::O, "O"),
+    (KeyCode::P, "P"),
+    (KeyCode::Q, "Q"),
+    (KeyCode::R, "R"),
+    (KeyCode::S, "S"),
+    (KeyCode::T, "T"),
+    (KeyCode::U, "U"),
+    (KeyCode::V, "V"),
+    (KeyCode::W, "W"),
+    (KeyCode::X, "X"),
+    (KeyCode::Y, "Y"),
+    (KeyCode::Z, "Z"),
+    (KeyCode::LeftBracket, "["),
+    (KeyCode::Backslash, "\\"),
+    (KeyCode::RightBracket, "]"),
+    (KeyCode::GraveAccent, "`"),
+    (KeyCode::Escape, "Escape"),
+    (KeyCode::Enter, "Enter"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Insert, "Insert"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageDown, "PageDown"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::End, "End"),
+    (KeyCode::F1, "F1"),
+    (KeyCode::F2, "F2"),
+    (KeyCode::F3, "F3"),
+    (KeyCode::F4, "F4"),
+    (KeyCode::F5, "F5"),
+    (KeyCode::F6, "F6"),
+    (KeyCode::F7, "F7"),
+    (KeyCode::F8, "F8"),
+    (KeyCode::F9, "F9"),
+    (KeyCode::F10, "F10"),
+    (KeyCode::F11, "F11"),
+    (KeyCode::F12, "F12"),
+    (KeyCode::Kp0, "Num0"),
+    (KeyCode::Kp1, "Num1"),
+    (KeyCode::Kp2, "Num2"),
+    (KeyCode::Kp3, "Num3"),
+    (KeyCode::Kp4, "Num4"),
+    (KeyCode::Kp5, "Num5"),
+    (KeyCode::Kp6, "Num6"),
+    (KeyCode::Kp7, "Num7"),
+    (KeyCode::Kp8, "Num8"),
+    (KeyCode::Kp9, "Num9"),
+    (KeyCode::KpEnter, "NumEnter"),
+    (KeyCode::LeftShift, "LeftShift"),
+    (KeyCode::LeftControl, "LeftControl"),
+    (KeyCode::LeftAlt, "LeftAlt"),
+    (KeyCode::RightShift, "RightShift"),
+    (KeyCode::RightControl, "RightControl"),
+    (KeyCode::RightAlt, "RightAlt"),
+];
+
+pub fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES.iter().find(|(k, _)| *k == key).map(|(_, name)| *name)
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    KEY_NAMES.iter().find(|(_, n)| n.eq_ignore_ascii_case(name)).map(|(key, _)| *key)
+}
@@ -0,0 +1,186 @@
+pub(crate) mod gamepad;
+pub(crate) mod keys;
+pub mod replay;
+
+use crate::input::gamepad::GamepadInput;
+use crate::input::keys::{key_from_name, key_name};
+use crate::storage;
+use macroquad::prelude::*;
+use nanoserde::{DeJson, SerJson};
+use std::collections::HashMap;
+
+const CONTROLS_FILE: &str = "controls.json";
+
+/// Menus move the selection with `Jump` and `Duck`.
+/// Gameplay reads the movement actions and `Pause`, menus read `Confirm` and `Back`, so one key may serve
+/// an action of each: escape pauses and goes back, space jumps and confirms. Within a menu `Back` is
+/// checked before `Confirm`, which is checked before moving the selection.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Duck,
+    Confirm,
+    Back,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Jump,
+        Action::Duck,
+        Action::Confirm,
+        Action::Back,
+        Action::Pause,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "MoveLeft",
+            Action::MoveRight => "MoveRight",
+            Action::Jump => "Jump",
+            Action::Duck => "Duck",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+            Action::Pause => "Pause",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Jump => "Jump",
+            Action::Duck => "Duck",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+            Action::Pause => "Pause",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|action| action.name() == name)
+    }
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        match self {
+            Action::MoveLeft => vec![KeyCode::A, KeyCode::Left],
+            Action::MoveRight => vec![KeyCode::D, KeyCode::Right],
+            Action::Jump => vec![KeyCode::Space, KeyCode::Up],
+            Action::Duck => vec![KeyCode::S, KeyCode::Down],
+            Action::Confirm => vec![KeyCode::Enter, KeyCode::Space],
+            Action::Back => vec![KeyCode::Escape, KeyCode::Q],
+            Action::Pause => vec![KeyCode::Escape, KeyCode::P],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, DeJson, SerJson)]
+struct ControlsFile {
+    bindings: Vec<Binding>,
+}
+
+#[derive(Clone, Debug, Default, DeJson, SerJson)]
+struct Binding {
+    action: String,
+    keys: Vec<String>,
+}
+
+/// The keys bound to every action, kept in `controls.json` in the data directory,
+/// plus the gamepads which have a fixed layout.
+pub struct Controls {
+    bindings: HashMap<Action, Vec<KeyCode>>,
+    gamepad: GamepadInput,
+}
+
+impl Controls {
+    /// Reads the controls file, actions missing from it keep their default keys.
+    pub fn load() -> Controls {
+        let mut controls = Controls {
+            bindings: default_bindings(),
+            gamepad: GamepadInput::new(),
+        };
+        let json = match storage::read(CONTROLS_FILE) {
+            Some(json) => json,
+            None => return controls,
+        };
+        let file = match ControlsFile::deserialize_json(&json) {
+            Ok(file) => file,
+            Err(e) => {
+                error!("ignoring broken controls file: {:?}", e);
+                return controls;
+            }
+        };
+        for binding in file.bindings {
+            let action = match Action::from_name(&binding.action) {
+                Some(action) => action,
+                None => {
+                    error!("unknown action '{}' in controls file", binding.action);
+                    continue;
+                }
+            };
+            let mut keys = Vec::with_capacity(binding.keys.len());
+            for name in &binding.keys {
+                match key_from_name(name) {
+                    Some(key) => keys.push(key),
+                    None => error!("unknown key '{}' for action '{}'", name, binding.action),
+                }
+            }
+            controls.bindings.insert(action, keys);
+        }
+        controls
+    }
+
+    pub fn write(&self) {
+        let file = ControlsFile {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| Binding {
+                    action: action.name().to_string(),
+                    keys: self.keys(*action).iter().filter_map(|key| key_name(*key)).map(String::from).collect(),
+                })
+                .collect(),
+        };
+        if let Err(e) = storage::write(CONTROLS_FILE, &file.serialize_json()) {
+            error!("could not write controls file: {}", e);
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Puts `key` in place of the first key of `action`, the other keys stay bound.
+    pub fn rebind(&mut self, action: Action, key: KeyCode) {
+        let keys = self.bindings.entry(action).or_default();
+        keys.retain(|bound| *bound != key);
+        match keys.first_mut() {
+            Some(first) => *first = key,
+            None => keys.push(key),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.bindings = default_bindings();
+    }
+
+    /// Reads the gamepads, called once per frame before any scene runs.
+    pub fn update(&mut self) {
+        self.gamepad.update();
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|key| is_key_down(*key)) || self.gamepad.is_down(action)
+    }
+
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|key| is_key_pressed(*key)) || self.gamepad.is_pressed(action)
+    }
+}
+
+fn default_bindings() -> HashMap<Action, Vec<KeyCode>> {
+    Action::ALL.iter().map(|action| (*action, action.default_keys())).collect()
+}
@@ -0,0 +1,161 @@
+use crate::entity::body::PlayerInput;
+use crate::entity::physics::PlayerPhysics;
+use crate::storage;
+use crate::storage::save::SavedPosition;
+use macroquad::prelude::*;
+use nanoserde::{DeJson, SerJson};
+
+const REPLAY_VERSION: u32 = 2;
+pub const LAST_RUN_FILE: &str = "last_run.json";
+
+/// A stretch of ticks with the same input, the input packed as bits.
+#[derive(Clone, Debug, Default, PartialEq, DeJson, SerJson)]
+struct Span {
+    input: u8,
+    ticks: u32,
+}
+
+/// The ticks from `tick` on were played with the physics of `fingerprint`.
+#[derive(Clone, Debug, Default, PartialEq, DeJson, SerJson)]
+struct PhysicsChange {
+    tick: u32,
+    fingerprint: u64,
+}
+
+/// The player input of every tick of a run, where the run started and the physics it was played with,
+/// played back through `Player::update` with the same physics it reproduces the run exactly.
+#[derive(Clone, Debug, Default, PartialEq, DeJson, SerJson)]
+pub struct Replay {
+    pub version: u32,
+    pub level: usize,
+    pub checkpoint: Option<SavedPosition>,
+    physics: Vec<PhysicsChange>,
+    spans: Vec<Span>,
+}
+
+impl Replay {
+    pub fn new(level: usize, checkpoint: Option<SavedPosition>, physics: &PlayerPhysics) -> Replay {
+        let mut replay = Replay {
+            version: REPLAY_VERSION,
+            level,
+            checkpoint,
+            physics: vec![],
+            spans: vec![],
+        };
+        replay.set_physics(physics);
+        replay
+    }
+
+    pub fn read(path: &str) -> Result<Replay, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("could not read replay '{}': {}", path, e))?;
+        let replay = Replay::deserialize_json(&json).map_err(|e| format!("invalid replay '{}': {:?}", path, e))?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!("replay '{}' has version {}, expected {}", path, replay.version, REPLAY_VERSION));
+        }
+        Ok(replay)
+    }
+
+    pub fn write(&self, name: &str) {
+        if let Err(e) = storage::write(name, &self.serialize_json()) {
+            error!("could not write replay: {}", e);
+        }
+    }
+
+    pub fn push(&mut self, input: &PlayerInput) {
+        let bits = pack(input);
+        match self.spans.last_mut() {
+            Some(span) if span.input == bits => span.ticks += 1,
+            _ => self.spans.push(Span { input: bits, ticks: 1 }),
+        }
+    }
+
+    pub fn set_physics(&mut self, physics: &PlayerPhysics) {
+        let fingerprint = physics.fingerprint();
+        if self.physics.last().map(|change| change.fingerprint) != Some(fingerprint) {
+            self.physics.push(PhysicsChange { tick: self.ticks(), fingerprint });
+        }
+    }
+
+    pub fn ticks(&self) -> u32 {
+        self.spans.iter().map(|span| span.ticks).sum()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    span: usize,
+    tick: u32,
+    played: u32,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> ReplayPlayer {
+        ReplayPlayer { replay, span: 0, tick: 0, played: 0 }
+    }
+
+    pub fn next_input(&mut self) -> Option<PlayerInput> {
+        let span = self.replay.spans.get(self.span)?;
+        let input = unpack(span.input);
+        self.played += 1;
+        self.tick += 1;
+        if self.tick >= span.ticks {
+            self.span += 1;
+            self.tick = 0;
+        }
+        Some(input)
+    }
+
+    /// Whether the ticks from now on were recorded with `physics`, with others the run plays out differently.
+    pub fn matches_physics(&self, physics: &PlayerPhysics) -> bool {
+        match self.replay.physics.iter().rev().find(|change| change.tick <= self.played) {
+            Some(change) => change.fingerprint == physics.fingerprint(),
+            None => true,
+        }
+    }
+}
+
+pub enum InputSource {
+    Live(Replay),
+    Replay(ReplayPlayer),
+}
+
+fn pack(input: &PlayerInput) -> u8 {
+    input.left as u8
+        | (input.right as u8) << 1
+        | (input.jump as u8) << 2
+        | (input.duck as u8) << 3
+        | (input.respawn as u8) << 4
+}
+
+fn unpack(bits: u8) -> PlayerInput {
+    PlayerInput {
+        left: bits & 1 != 0,
+        right: bits & 1 << 1 != 0,
+        jump: bits & 1 << 2 != 0,
+        duck: bits & 1 << 3 != 0,
+        respawn: bits & 1 << 4 != 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_know_the_physics_of_every_tick() {
+        let physics = PlayerPhysics::default();
+        let slippery = PlayerPhysics { break_speed_curve: vec![3.0, 2.0, 1.0], ..PlayerPhysics::default() };
+        let mut replay = Replay::new(0, None, &physics);
+        replay.push(&PlayerInput::default());
+        replay.set_physics(&slippery);
+        replay.push(&PlayerInput { jump: true, ..PlayerInput::default() });
+        let json = replay.serialize_json();
+        let mut player = ReplayPlayer::new(Replay::deserialize_json(&json).unwrap());
+        assert!(player.matches_physics(&physics));
+        assert!(!player.matches_physics(&slippery));
+        player.next_input();
+        assert!(player.matches_physics(&slippery), "the second tick was played with other physics");
+        assert!(!player.matches_physics(&physics));
+    }
+}
@@ -0,0 +1,26 @@
+pub mod assets;
+pub mod audio;
+pub mod entity;
+pub mod input;
+pub mod scene;
+pub mod storage;
+pub mod tilemap;
+pub mod utils;
+
+use macroquad::prelude::*;
+
+pub const FONT_COLOR: Color = color_u8!(202, 202, 202, 255);
+pub const DEBUG: bool = false;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum MainState {
+    TITLE,
+    STORY,
+    GAME,
+    EXIT,
+    RUN,
+    END,
+    CONTROLS,
+    PAUSE,
+    SETTINGS,
+}
\ No newline at end of file
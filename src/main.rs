@@ -1,88 +1,154 @@
-mod entity;
-mod scene;
-mod tilemap;
-mod utils;
-
 use macroquad::prelude::*;
 
-use crate::scene::title::Title;
-use crate::scene::game::Game;
-use crate::scene::end::End;
-
-use quad_snd::decoder;
-use quad_snd::mixer::{Volume, SoundMixer, PlaybackStyle};
-
-const FONT_COLOR: Color = color_u8!(202, 202, 202, 255);
-const GAME_ZOOM: f32 = 6.0;
-const TITLE_ZOOM: f32 = 6.0;
-const DEBUG: bool = false;
-
-const MUSIC_BYTES: &[u8] = include_bytes!("../assets/music/start.ogg");
+use rustyjam21::audio::{Audio, CROSSFADE, TITLE_MUSIC};
+use rustyjam21::input::replay::Replay;
+use rustyjam21::input::Controls;
+use rustyjam21::scene::controls::ControlsMenu;
+use rustyjam21::scene::title::Title;
+use rustyjam21::scene::game::Game;
+use rustyjam21::scene::pause::Pause;
+use rustyjam21::scene::settings::SettingsMenu;
+use rustyjam21::scene::end::End;
+use rustyjam21::storage::save::SaveGame;
+use rustyjam21::storage::settings::Settings;
+use rustyjam21::MainState;
 
 #[macroquad::main(window_conf)]
 async fn main() {
+    let replay = match replay_arg() {
+        Some(path) => match Replay::read(&path) {
+            Ok(replay) => Some(replay),
+            Err(e) => {
+                error!("{}", e);
+                return;
+            }
+        },
+        None => None,
+    };
     let mut main_state = MainState::TITLE;
-    let mut title = Title::init().await;
-    let mut game = Game::init().await;
-    let mut end = End::init().await;
-    let mut mixer = SoundMixer::new();
-    let mut sound = decoder::read_ogg(MUSIC_BYTES).unwrap();
-    sound.playback_style = PlaybackStyle::Looped;
-    let sound_id = mixer.play(sound);
-    mixer.set_volume(sound_id, Volume(0.6));
+    let mut save = SaveGame::load();
+    let mut settings = Settings::load();
+    let mut controls = Controls::load();
+    let mut audio = match Audio::init(&settings).await {
+        Ok(audio) => audio,
+        Err(e) => {
+            error!("could not load the sounds: {}", e);
+            return;
+        }
+    };
+    let mut title = Title::init(&save, &settings).await;
+    let mut game = match Game::init(&settings).await {
+        Ok(game) => game,
+        Err(e) => {
+            error!("could not load the game map: {}", e);
+            return;
+        }
+    };
+    let mut end = End::init(&settings).await;
+    let mut controls_menu = ControlsMenu::init().await;
+    let mut pause = Pause::init().await;
+    let mut settings_menu = SettingsMenu::init(&settings).await;
+    if let Some(replay) = replay {
+        match game.play_replay(replay) {
+            Ok(()) => main_state = MainState::GAME,
+            Err(e) => error!("not playing the replay: {}", e),
+        }
+    }
+    audio.play_music(TITLE_MUSIC, 0.0);
     loop {
         clear_background(BLACK);
+        controls.update();
         match main_state {
             MainState::EXIT => break,
             MainState::TITLE => {
-                if let Some(gs) = title.run() {
-                    if gs == MainState::GAME {
-                        game.reset();
+                if let Some(gs) = title.run(&controls) {
+                    if gs == MainState::SETTINGS {
+                        settings_menu.open(MainState::TITLE);
+                    } else if gs == MainState::GAME {
+                        if title.continue_selected() {
+                            game.continue_game(&save);
+                        } else {
+                            game.new_game(&mut save);
+                        }
                     }
                     main_state = gs
                 }
             }
             MainState::GAME => {
-                if let Some(gs) = game.run() {
-                    if gs == MainState::END {
-                        mixer.stop(sound_id);
+                if let Some(gs) = game.run(&mut save, &controls, &mut audio) {
+                    if gs == MainState::PAUSE {
+                        pause.open(&game);
+                    }
+                    main_state = gs
+                }
+            }
+            MainState::PAUSE => {
+                if let Some(gs) = pause.run(&mut game, &mut save, &controls) {
+                    if gs == MainState::SETTINGS {
+                        settings_menu.open(MainState::PAUSE);
+                    } else if gs == MainState::TITLE {
+                        title.reset(&save);
                     }
                     main_state = gs
                 }
             }
             MainState::END => {
-                if let Some(gs) = end.run(&mut mixer) {
+                if let Some(gs) = end.run(&mut audio, &save, game.run_timer(), game.new_best_run(), &controls) {
                     if gs == MainState::TITLE {
-                        title.reset();
+                        title.reset(&save);
+                        audio.play_music(TITLE_MUSIC, CROSSFADE);
+                    }
+                    main_state = gs
+                }
+            }
+            MainState::CONTROLS => {
+                if let Some(gs) = controls_menu.run(&mut controls) {
+                    main_state = gs
+                }
+            }
+            MainState::SETTINGS => {
+                let gs = settings_menu.run(&mut settings, &controls);
+                audio.apply_settings(&settings);
+                title.apply_settings(&settings);
+                game.apply_settings(&settings);
+                end.apply_settings(&settings);
+                if let Some(gs) = gs {
+                    if gs == MainState::CONTROLS {
+                        controls_menu.open(MainState::SETTINGS);
+                    } else if gs == MainState::TITLE {
+                        title.resume(&save);
                     }
                     main_state = gs
                 }
             }
             _ => {}
         }
-        mixer.frame();
+        audio.frame();
         next_frame().await;
         //std:: thread ::sleep(Duration::from_millis(10));
     }
 }
 
+/// The file given with `--replay <file>`, the game then starts right away driven by the recording.
+fn replay_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--replay" {
+            return args.next();
+        }
+    }
+    None
+}
+
 fn window_conf() -> Conf {
+    let settings = Settings::load();
+    let (window_width, window_height) = settings.window_size();
     Conf {
         window_title: "RustyJam2021".to_owned(),
-        window_width: 800,
-        window_height: 800,
+        window_width,
+        window_height,
         high_dpi: false,
-        fullscreen: false,
+        fullscreen: settings.fullscreen,
         ..Default::default()
     }
 }
-
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub enum MainState {
-    TITLE,
-    STORY,
-    GAME,
-    EXIT,
-    RUN,
-    END,
-}
\ No newline at end of file
@@ -0,0 +1,123 @@
+use crate::assets;
+use crate::input::keys::key_name;
+use crate::input::{Action, Controls};
+use crate::{MainState, FONT_COLOR};
+use macroquad::prelude::*;
+
+const FONT_SIZE: u16 = 80;
+const FONT_SCALE: f32 = 0.4;
+const LINE_HEIGHT: f32 = 44.0;
+
+/// Menu that lists the key bindings and replaces the first key of the selected action with the next key pressed.
+pub struct ControlsMenu {
+    font: Font,
+    selected: usize,
+    listening: bool,
+    back_to: MainState,
+}
+
+impl ControlsMenu {
+    pub async fn init() -> ControlsMenu {
+        let font = assets::load_font("fonts/Born2bSportyAkan.ttf").await.unwrap();
+        ControlsMenu {
+            font,
+            selected: 0,
+            listening: false,
+            back_to: MainState::TITLE,
+        }
+    }
+
+    pub fn open(&mut self, back_to: MainState) {
+        self.selected = 0;
+        self.listening = false;
+        self.back_to = back_to;
+    }
+
+    pub fn run(&mut self, controls: &mut Controls) -> Option<MainState> {
+        set_default_camera();
+        let state = if self.listening {
+            self.listen(controls);
+            None
+        } else {
+            self.process_action(controls)
+        };
+        self.draw(controls);
+        state
+    }
+
+    fn entry_count(&self) -> usize {
+        Action::ALL.len() + 2
+    }
+
+    fn process_action(&mut self, controls: &mut Controls) -> Option<MainState> {
+        if controls.is_pressed(Action::Back) {
+            controls.write();
+            return Some(self.back_to.clone());
+        }
+        if controls.is_pressed(Action::Confirm) {
+            match self.selected {
+                i if i < Action::ALL.len() => self.listening = true,
+                i if i == Action::ALL.len() => controls.reset(),
+                _ => {
+                    controls.write();
+                    return Some(self.back_to.clone());
+                }
+            }
+        } else if controls.is_pressed(Action::Jump) {
+            self.selected = (self.selected + self.entry_count() - 1) % self.entry_count();
+        } else if controls.is_pressed(Action::Duck) {
+            self.selected = (self.selected + 1) % self.entry_count();
+        }
+        None
+    }
+
+    /// Waits for a key to bind to the selected action, escape cancels.
+    fn listen(&mut self, controls: &mut Controls) {
+        if let Some(key) = get_last_key_pressed() {
+            if key != KeyCode::Escape && key_name(key).is_some() {
+                controls.rebind(Action::ALL[self.selected], key);
+            }
+            self.listening = false;
+        }
+    }
+
+    fn draw(&self, controls: &Controls) {
+        let x = screen_width() / 2.0 - 240.0;
+        let mut y = screen_height() / 2.0 - LINE_HEIGHT * (self.entry_count() as f32 / 2.0 + 1.0);
+        self.draw_line("Controls", x, y, FONT_COLOR);
+        y += LINE_HEIGHT * 1.5;
+        for (i, action) in Action::ALL.iter().enumerate() {
+            let keys = if self.listening && i == self.selected {
+                "press a key...".to_string()
+            } else {
+                controls.keys(*action).iter().filter_map(|key| key_name(*key)).collect::<Vec<_>>().join(", ")
+            };
+            let color = self.color(i);
+            self.draw_line(action.label(), x, y, color);
+            self.draw_line(&keys, x + 240.0, y, color);
+            y += LINE_HEIGHT;
+        }
+        y += LINE_HEIGHT / 2.0;
+        self.draw_line("Reset to defaults", x, y, self.color(Action::ALL.len()));
+        self.draw_line("Back", x, y + LINE_HEIGHT, self.color(Action::ALL.len() + 1));
+    }
+
+    fn color(&self, entry: usize) -> Color {
+        if entry == self.selected {
+            FONT_COLOR
+        } else {
+            DARKGRAY
+        }
+    }
+
+    fn draw_line(&self, text: &str, x: f32, y: f32, color: Color) {
+        let tp = TextParams {
+            font: self.font,
+            font_size: FONT_SIZE,
+            font_scale: FONT_SCALE,
+            font_scale_aspect: 1.0,
+            color,
+        };
+        draw_text_ex(text, x, y, tp);
+    }
+}
@@ -1,26 +1,28 @@
-use crate::{MainState, FONT_COLOR, TITLE_ZOOM};
+use crate::assets;
+use crate::audio::{Audio, CROSSFADE, END_MUSIC};
+use crate::input::{Action, Controls};
+use crate::storage::save::SaveGame;
+use crate::storage::settings::Settings;
+use crate::utils::run_timer::{format_ticks, RunTimer};
+use crate::{MainState, FONT_COLOR};
 use macroquad::prelude::*;
-use quad_snd::decoder;
-use quad_snd::mixer::{SoundMixer, Volume, SoundId, PlaybackStyle};
-
-const MUSIC_BYTES: &[u8] = include_bytes!("../../assets/music/end.ogg");
 
 pub struct End {
     camera: Camera2D,
     font: Font,
     text1: Vec<String>,
     start: bool,
-    sound_id: Option<SoundId>,
+    zoom: f32,
 }
 
 impl End {
-    pub async fn init() -> End {
+    pub async fn init(settings: &Settings) -> End {
         let camera = Camera2D {
-            zoom: vec2(TITLE_ZOOM / screen_width() * 2.0, -TITLE_ZOOM / screen_height() * 2.0),
+            zoom: vec2(settings.zoom() / screen_width() * 2.0, -settings.zoom() / screen_height() * 2.0),
             target: vec2(0.0, 0.0),
             ..Default::default()
         };
-        let font = load_ttf_font_from_bytes(include_bytes!("../../assets/fonts/Born2bSportyAkan.ttf"));
+        let font = assets::load_font("fonts/Born2bSportyAkan.ttf").await;
         let t1 = "You have found the exit!\n\nThe world with all its illusions\nis waiting for you.\n\nThanks for playing.\n\n";
         let text1 = t1.to_string().split('\n').map(String::from).collect();
         End { 
@@ -28,18 +30,19 @@ impl End {
             font: font.unwrap(),
             text1, 
             start: true,
-            sound_id: None
+            zoom: settings.zoom(),
         }
     }
 
-    pub fn run(&mut self, mixer: &mut SoundMixer) -> Option<MainState> {
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.zoom = settings.zoom();
+    }
+
+    /// `new_best` tells whether the run beat the best run time, which `save` already holds.
+    pub fn run(&mut self, audio: &mut Audio, save: &SaveGame, timer: &RunTimer, new_best: bool, controls: &Controls) -> Option<MainState> {
         if self.start {
-            let mut sound  = decoder::read_ogg(MUSIC_BYTES).unwrap();
-            sound.playback_style = PlaybackStyle::Looped;
-            let id = mixer.play(sound);
-            mixer.set_volume(id, Volume(0.6));
+            audio.play_music(END_MUSIC, CROSSFADE);
             self.start = false;
-            self.sound_id = Some(id);
         }
         update_camera(self, vec2(0.0, 0.0));
         set_camera(&self.camera);
@@ -55,20 +58,45 @@ impl End {
             draw_text_ex(
                 line,
                 (screen_width() / 2.0) - 220.0,
-                (screen_height() / 2.0) - 220.0 + i as f32 * 80.0,
+                (screen_height() / 2.0) - 260.0 + i as f32 * 64.0,
+                tp,
+            );
+        }
+        let tp = TextParams { font_scale: 0.4, ..tp };
+        for (i, line) in run_summary(save, timer, new_best).iter().enumerate() {
+            draw_text_ex(
+                line,
+                (screen_width() / 2.0) - 220.0,
+                (screen_height() / 2.0) + 120.0 + i as f32 * 36.0,
                 tp,
             );
         }
 
-        if get_last_key_pressed().is_some() {
-            mixer.stop(self.sound_id.unwrap());
+        if controls.is_pressed(Action::Confirm) || controls.is_pressed(Action::Back) {
+            self.start = true;
             return Some(MainState::TITLE);
         }
         None
     }
 }
 
+fn run_summary(save: &SaveGame, timer: &RunTimer, new_best: bool) -> Vec<String> {
+    let mut lines = Vec::with_capacity(timer.splits().len() + 2);
+    let time = format_ticks(timer.ticks());
+    lines.push(match save.best_run_ticks {
+        _ if new_best => format!("Time {}   new personal best!", time),
+        Some(best) => format!("Time {}   best {}", time, format_ticks(best)),
+        None => format!("Time {}", time),
+    });
+    lines.push(format!("Deaths {}", timer.deaths()));
+    for split in timer.splits() {
+        let best = save.best_level_ticks.get(&split.level).copied().unwrap_or(split.ticks);
+        lines.push(format!("{}   {}   best {}", split.level, format_ticks(split.ticks), format_ticks(best)));
+    }
+    lines
+}
+
 fn update_camera(scene: &mut End, new_target: Vec2) {
     scene.camera.target = new_target;
-    scene.camera.zoom = vec2(TITLE_ZOOM / screen_width() * 2.0, -TITLE_ZOOM / screen_height() * 2.0);
+    scene.camera.zoom = vec2(scene.zoom / screen_width() * 2.0, -scene.zoom / screen_height() * 2.0);
 }
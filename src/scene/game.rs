@@ -1,6 +1,18 @@
+use crate::assets;
+use crate::assets::watcher::FileWatcher;
+use crate::audio::Audio;
+use crate::entity::body::PlayerInput;
+use crate::entity::physics::{PlayerPhysics, PHYSICS_FILE};
 use crate::entity::player::Player;
-use crate::tilemap::Tilemap;
-use crate::{MainState, GAME_ZOOM};
+use crate::input::replay::{InputSource, Replay, ReplayPlayer, LAST_RUN_FILE};
+use crate::input::{Action, Controls};
+use crate::scene::level::{load_levels, Level, LevelSource};
+use crate::storage::save::SaveGame;
+use crate::storage::settings::Settings;
+use crate::tilemap::error::TilemapError;
+use crate::utils::fixed_step::FixedStep;
+use crate::utils::run_timer::{format_ticks, RunTimer};
+use crate::{MainState, FONT_COLOR};
 use macroquad::prelude::*;
 
 #[allow(dead_code)]
@@ -13,82 +25,419 @@ pub enum GameState {
 
 pub struct Game {
     player: Player,
-    game_texture: Texture2D,
-    game_tilemap: Tilemap,
+    levels: Vec<LevelSource>,
+    level: Level,
+    level_index: usize,
+    watcher: FileWatcher,
+    physics: PlayerPhysics,
+    physics_watcher: FileWatcher,
+    timer: RunTimer,
+    new_best_run: bool,
+    font: Font,
+    input: PlayerInput,
+    source: InputSource,
+    step: FixedStep,
     camera: Camera2D,
+    zoom: f32,
     game_state: GameState,
 }
 
 impl Game {
-    pub async fn init() -> Game {
-        let game_texture = get_map_texture();
-        let game_tilemap = get_game_tilemap(&game_texture);
-        let mut player = Player::new();
-        player.reset(&game_tilemap);
+    pub async fn init(settings: &Settings) -> Result<Game, TilemapError> {
+        let levels = load_levels().await?;
+        let (level_index, level) = load_level_from(&levels, 0)?;
+        let physics = load_physics().await;
+        let mut player = Player::new().await;
+        player.set_physics(level_physics(&physics, &levels[level_index], &level));
+        player.reset(&level.tilemap, &level.layers, &level.properties);
+        let watcher = watch_level(&levels[level_index]);
+        let font = assets::load_font("fonts/Born2bSportyAkan.ttf").await?;
+        let recording = Replay::new(level_index, None, player.physics());
 
         let camera = Camera2D {
-            zoom: vec2(GAME_ZOOM / screen_width() * 2.0, -GAME_ZOOM / screen_height() * 2.0),
+            zoom: vec2(settings.zoom() / screen_width() * 2.0, -settings.zoom() / screen_height() * 2.0),
             target: Vec2::new(64.0,64.0),
             ..Default::default()
         };
 
-        Game {
+        Ok(Game {
             player,
-            game_texture,
-            game_tilemap,
+            levels,
+            level,
+            level_index,
+            watcher,
+            physics,
+            physics_watcher: FileWatcher::new(&[PHYSICS_FILE.to_string()]),
+            timer: RunTimer::new(),
+            new_best_run: false,
+            font,
+            input: PlayerInput::default(),
+            source: InputSource::Live(recording),
+            step: FixedStep::new(),
             camera,
+            zoom: settings.zoom(),
             game_state: GameState::GAME,
+        })
+    }
+
+    pub fn new_game(&mut self, save: &mut SaveGame) {
+        save.new_game();
+        save.write();
+        self.reset();
+        self.timer.start(0, 0, 0);
+        self.source = InputSource::Live(Replay::new(self.level_index, None, self.player.physics()));
+    }
+
+    pub fn continue_game(&mut self, save: &SaveGame) {
+        self.game_state = GameState::GAME;
+        let at_level = if save.level == self.level_index {
+            self.restart_current_level();
+            true
+        } else {
+            self.change_level(save.level)
+        };
+        if !at_level {
+            error!("could not continue at level {}, starting over", save.level);
+            self.reset();
+            self.timer.start(0, 0, save.deaths);
+        } else {
+            self.player.set_checkpoint(save.checkpoint.map(Vec2::from));
+            self.player.reset(&self.level.tilemap, &self.level.layers, &self.level.properties);
+            self.step.reset();
+            self.timer.start(save.run_ticks, save.level_ticks, save.deaths);
         }
+        let checkpoint = self.player.checkpoint().map(Into::into);
+        self.source = InputSource::Live(Replay::new(self.level_index, checkpoint, self.player.physics()));
+    }
+
+    /// Drives the player from a recording instead of the controls, progress is not saved meanwhile.
+    /// Fails if the run can not be picked up where the recording started it.
+    pub fn play_replay(&mut self, replay: Replay) -> Result<(), String> {
+        let save = SaveGame {
+            level: replay.level,
+            checkpoint: replay.checkpoint,
+            ..Default::default()
+        };
+        self.continue_game(&save);
+        if self.level_index != replay.level {
+            return Err(format!("level {} of the recording could not be loaded", replay.level));
+        }
+        if let Some(checkpoint) = replay.checkpoint.map(Vec2::from) {
+            let fits = self.player.fits_on(&self.level.tilemap, &self.level.layers, &self.level.properties);
+            if !self.level.tilemap.is_inside_map(checkpoint) || !fits {
+                return Err(format!("the player does not fit at the checkpoint {} of the recording", checkpoint));
+            }
+        }
+        info!("playing replay of {} ticks", replay.ticks());
+        let replay = ReplayPlayer::new(replay);
+        check_replay_physics(&replay, self.player.physics());
+        self.source = InputSource::Replay(replay);
+        Ok(())
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.zoom = settings.zoom();
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self.source, InputSource::Replay(_))
     }
 
     pub fn reset(&mut self) {
         self.game_state = GameState::GAME;
-        self.player.reset(&self.game_tilemap)
+        self.player.clear_checkpoint();
+        if self.level_index == 0 {
+            self.restart_current_level();
+        } else if !self.change_level(0) {
+            error!("no level left to restart from");
+            self.restart_current_level();
+        }
+        self.player.reset(&self.level.tilemap, &self.level.layers, &self.level.properties);
+        self.step.reset();
+    }
+
+    /// Starts the current level over from its spawn, the run timer keeps going.
+    /// The recording starts over as well, the input before the restart can not be played back from the spawn.
+    pub fn restart_level(&mut self, save: &mut SaveGame) {
+        self.game_state = GameState::GAME;
+        self.restart_current_level();
+        self.player.clear_checkpoint();
+        self.player.reset(&self.level.tilemap, &self.level.layers, &self.level.properties);
+        self.step.reset();
+        self.source = InputSource::Live(Replay::new(self.level_index, None, self.player.physics()));
+        save.checkpoint = None;
+        self.persist(save);
+    }
+
+    fn change_level(&mut self, index: usize) -> bool {
+        match load_level_from(&self.levels, index) {
+            Ok((level_index, level)) => {
+                self.level.unload();
+                self.level = level;
+                self.level_index = level_index;
+                self.watcher = watch_level(&self.levels[level_index]);
+                self.update_physics();
+                self.player.clear_checkpoint();
+                self.player.reset(&self.level.tilemap, &self.level.layers, &self.level.properties);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Hands the player the tunables of the current level, a recording notes them and a replay checks them.
+    fn update_physics(&mut self) {
+        let physics = level_physics(&self.physics, &self.levels[self.level_index], &self.level);
+        match &mut self.source {
+            InputSource::Live(recording) => recording.set_physics(&physics),
+            InputSource::Replay(replay) => check_replay_physics(replay, &physics),
+        }
+        self.player.set_physics(physics);
+    }
+
+    fn restart_current_level(&mut self) {
+        let source = &self.levels[self.level_index];
+        if let Err(e) = self.level.restart(source) {
+            error!("could not restart level '{}': {}", source.info.name, e);
+        }
+    }
+
+    fn reload_level(&mut self) {
+        let source = &mut self.levels[self.level_index];
+        let map = assets::read_from_disk(&source.info.map_path());
+        let tileset = assets::read_from_disk(&source.info.tileset_path());
+        let extra_tilesets: Result<Vec<_>, _> = source.info.extra_tileset_paths().iter().map(|path| assets::read_from_disk(path)).collect();
+        let properties = assets::read_from_disk(&source.info.properties_path());
+        let physics = match source.info.physics_path() {
+            Some(path) => assets::read_from_disk(&path),
+            None => Ok(vec![]),
+        };
+        match (map, tileset, extra_tilesets, properties, physics) {
+            (Ok(map), Ok(tileset), Ok(extra_tilesets), Ok(properties), Ok(physics)) => {
+                source.map = map;
+                source.tileset = tileset;
+                source.extra_tilesets = extra_tilesets;
+                source.properties = properties;
+                source.physics = physics;
+            }
+            (Err(e), _, _, _, _) | (_, Err(e), _, _, _) | (_, _, Err(e), _, _) | (_, _, _, Err(e), _) | (_, _, _, _, Err(e)) => {
+                error!("could not reload level '{}': {}", source.info.name, e);
+                return;
+            }
+        }
+        match Level::load(source) {
+            Ok(level) => {
+                info!("reloaded level '{}'", source.info.name);
+                self.level.unload();
+                self.level = level;
+                self.update_physics();
+                if !self.player.fits_on(&self.level.tilemap, &self.level.layers, &self.level.properties) {
+                    self.player.reset(&self.level.tilemap, &self.level.layers, &self.level.properties);
+                }
+            }
+            Err(e) => error!("could not reload level '{}': {}", source.info.name, e),
+        }
+    }
+
+    /// Rereads the common movement tunables from disk, a broken file keeps the current ones.
+    fn reload_physics(&mut self) {
+        let json = assets::read_from_disk(PHYSICS_FILE).map_err(|e| e.to_string()).and_then(|bytes| {
+            String::from_utf8(bytes).map_err(|e| e.to_string())
+        });
+        match json.and_then(|json| PlayerPhysics::from_json(&json)) {
+            Ok(physics) => {
+                info!("reloaded {}", PHYSICS_FILE);
+                self.physics = physics;
+                self.update_physics();
+            }
+            Err(e) => error!("could not reload {}: {}", PHYSICS_FILE, e),
+        }
+    }
+
+    /// Progress is written to `save` when a level is finished or a new checkpoint is reached.
+    pub fn run(&mut self, save: &mut SaveGame, controls: &Controls, audio: &mut Audio) -> Option<MainState> {
+        if self.watcher.changed() {
+            self.reload_level();
+        }
+        if self.physics_watcher.changed() {
+            self.reload_physics();
+        }
+        // a replay must not touch the progress of the player
+        let mut replay_save;
+        let save = if self.is_replay() {
+            replay_save = save.clone();
+            &mut replay_save
+        } else {
+            self.input.sample(controls);
+            save
+        };
+        for _ in 0..self.step.ticks() {
+            if let Some(main_state) = self.tick(save, audio) {
+                return Some(main_state);
+            }
+        }
+        self.draw();
+        if controls.is_pressed(Action::Pause) {
+            return Some(MainState::PAUSE);
+        }
+        None
+    }
+
+    pub fn draw(&mut self) {
+        update_camera(self, self.player.position());
+        set_camera(&self.camera);
+        self.level.tilemap.draw(&self.level.textures, vec2(0.0, 0.0), None);
+        self.player.draw();
+        self.draw_hud();
+    }
+
+    pub fn run_timer(&self) -> &RunTimer {
+        &self.timer
+    }
+
+    pub fn new_best_run(&self) -> bool {
+        self.new_best_run
+    }
+
+    fn draw_hud(&self) {
+        set_default_camera();
+        let tp = TextParams {
+            font: self.font,
+            font_size: 80,
+            font_scale: 0.4,
+            font_scale_aspect: 1.0,
+            color: FONT_COLOR,
+        };
+        draw_text_ex(&format_ticks(self.timer.ticks()), 20.0, 40.0, tp);
+        draw_text_ex(&format!("deaths {}", self.timer.deaths()), 20.0, 80.0, tp);
     }
 
-    pub fn run(&mut self) -> Option<MainState> {
-        let mut main_state= None;
-        if let Some(gs) = self.player.update(&mut self.game_tilemap) {
+    fn tick(&mut self, save: &mut SaveGame, audio: &mut Audio) -> Option<MainState> {
+        let checkpoint = self.player.checkpoint();
+        let input = match &mut self.source {
+            InputSource::Live(recording) => {
+                let input = self.input;
+                self.input.respawn = false;
+                recording.push(&input);
+                input
+            }
+            InputSource::Replay(replay) => match replay.next_input() {
+                Some(input) => input,
+                None => {
+                    info!("replay finished");
+                    return Some(MainState::TITLE);
+                }
+            },
+        };
+        self.timer.tick();
+        let player_state = self.player.update(&mut self.level.tilemap, &self.level.layers, &self.level.properties, &input, audio);
+        self.level.illusions.update(&mut self.level.tilemap, &self.level.layers);
+        if let Some(gs) = player_state {
             match gs {
                 GameState::WIN => {
-                    main_state = Some(MainState::END);
+                    // a replay plays on a copy of the save, its times are no personal bests
+                    let record = !self.is_replay();
+                    let name = &self.levels[self.level_index].info.name;
+                    let ticks = self.timer.split(name);
+                    if record && save.record_level(name, ticks) {
+                        info!("new best time for '{}': {}", name, format_ticks(ticks));
+                    }
+                    let finished = !self.change_level(self.level_index + 1);
+                    if finished {
+                        self.timer.stop();
+                        self.new_best_run = record && save.record_run(self.timer.ticks());
+                        if self.new_best_run {
+                            info!("new best run: {}", format_ticks(self.timer.ticks()));
+                        }
+                        save.finish_game();
+                    } else {
+                        save.level = self.level_index;
+                        save.checkpoint = None;
+                    }
+                    self.persist(save);
+                    if finished {
+                        return Some(MainState::END);
+                    }
                 }
                 GameState::DEAD => {
-                    self.player.reset(&self.game_tilemap);
+                    self.timer.die();
+                    self.player.reset(&self.level.tilemap, &self.level.layers, &self.level.properties);
                 }
                 _ => {
                     self.game_state = gs;
                 }
             }
+        } else if self.player.checkpoint() != checkpoint {
+            save.level = self.level_index;
+            save.checkpoint = self.player.checkpoint().map(Into::into);
+            self.persist(save);
+        }
+        None
+    }
 
+    /// Writes the save and the recording of the run so far, replays write nothing.
+    pub fn persist(&self, save: &mut SaveGame) {
+        if let InputSource::Live(recording) = &self.source {
+            save.run_ticks = self.timer.ticks();
+            save.level_ticks = self.timer.level_ticks();
+            save.deaths = self.timer.deaths();
+            save.write();
+            recording.write(LAST_RUN_FILE);
         }
-        update_camera(self, self.player.position());
-        set_camera(&self.camera);
-        self.game_tilemap.draw(self.game_texture, vec2(0.0, 0.0), None);
-        self.player.draw();
-        main_state
     }
 }
 
 fn update_camera(game: &mut Game, new_target: Vec2) {
     game.camera.target.x = new_target.x.round();
     game.camera.target.y = new_target.y.round();
-    game.camera.zoom = vec2(GAME_ZOOM / screen_width() * 2.0, -GAME_ZOOM / screen_height() * 2.0);
+    game.camera.zoom = vec2(game.zoom / screen_width() * 2.0, -game.zoom / screen_height() * 2.0);
 }
 
-fn get_map_texture() -> Texture2D {
-    let image = Image::from_file_with_format(include_bytes!("../../assets/maps/game.png"), None);
-    let texture: Texture2D = Texture2D::from_image(&image);
-    texture.set_filter(FilterMode::Nearest);
-    texture
+fn watch_level(source: &LevelSource) -> FileWatcher {
+    let mut paths = vec![source.info.map_path(), source.info.tileset_path(), source.info.properties_path()];
+    paths.extend(source.info.extra_tileset_paths());
+    paths.extend(source.info.physics_path());
+    FileWatcher::new(&paths)
 }
 
-fn get_game_tilemap(texture2d: &Texture2D) -> Tilemap {
-    let tiles_json_vec = include_bytes!("../../assets/maps/game.json").to_vec();
-    let tileset_image_rect = Rect::new(0.0,0.0,64.0,64.0);
-    let mut tilemap = Tilemap::from_pyxeledit(tileset_image_rect,String::from_utf8(tiles_json_vec).unwrap().as_str());
-    tilemap.set_tile_rectangles_from(texture2d);
-    tilemap.visibility(tilemap.get_layer_id("logic"), false);
-    tilemap.visibility(tilemap.get_layer_id("collision"), false);
-    tilemap
+async fn load_physics() -> PlayerPhysics {
+    match assets::load_string(PHYSICS_FILE).await {
+        Ok(json) => PlayerPhysics::from_json(&json).unwrap_or_else(|e| {
+            error!("ignoring broken {}: {}", PHYSICS_FILE, e);
+            PlayerPhysics::default()
+        }),
+        Err(e) => {
+            error!("{}, using the default physics", e);
+            PlayerPhysics::default()
+        }
+    }
+}
+
+/// The common tunables with the changes of `level`, a level breaking them keeps the common ones.
+fn level_physics(physics: &PlayerPhysics, source: &LevelSource, level: &Level) -> PlayerPhysics {
+    physics.with(&level.physics).unwrap_or_else(|e| {
+        error!("ignoring the physics of level '{}': {}", source.info.name, e);
+        physics.clone()
+    })
+}
+
+fn check_replay_physics(replay: &ReplayPlayer, physics: &PlayerPhysics) {
+    if !replay.matches_physics(physics) {
+        warn!("the replay was recorded with other physics, it will not play out the same");
+    }
+}
+
+/// Loads the first level from `index` on that is playable, broken maps are reported and skipped.
+fn load_level_from(levels: &[LevelSource], index: usize) -> Result<(usize, Level), TilemapError> {
+    let mut last_error = TilemapError::MissingField("levels");
+    for (i, source) in levels.iter().enumerate().skip(index) {
+        match Level::load(source) {
+            Ok(level) => return Ok((i, level)),
+            Err(e) => {
+                error!("skipping level '{}': {}", source.info.name, e);
+                last_error = e;
+            }
+        }
+    }
+    Err(last_error)
 }
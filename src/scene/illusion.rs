@@ -0,0 +1,102 @@
+use crate::scene::level::MapLayers;
+use crate::scene::tile_properties::TileProperties;
+use crate::tilemap::{LayerId, Tilemap};
+use crate::utils::fixed_step::TICK_RATE;
+use macroquad::prelude::*;
+
+const REVEAL_TICKS: u32 = TICK_RATE / 2;
+/// How much of a fake wall is still drawn once it is revealed.
+const REVEALED_WALL_ALPHA: f32 = 0.35;
+
+/// Reveals the illusion the logic tile at `position` belongs to, together with all touching tiles of the same kind.
+/// The tiles are swapped for their `reveals_as` id, so the reveal survives respawns and the effect can see it.
+pub fn reveal(tilemap: &mut Tilemap, logic: LayerId, properties: &TileProperties, position: Vec2) -> bool {
+    let id = tilemap.get_id_at_position(logic, position);
+    let revealed_id = match properties.logic(id) {
+        Some(property) if property.fake_wall || property.hidden_platform => match property.reveals_as {
+            Some(revealed_id) => revealed_id,
+            None => return false,
+        },
+        _ => return false,
+    };
+    let size = tilemap.tile_size();
+    let mut open = vec![tilemap.snap_to_tile(position)];
+    while let Some(tile) = open.pop() {
+        if !tilemap.is_inside_map(tile) || tilemap.get_id_at_position(logic, tile) != id {
+            continue;
+        }
+        tilemap.set_tileid_at(logic, Some(revealed_id), tile);
+        open.push(tile - vec2(size.x, 0.0));
+        open.push(tile + vec2(size.x, 0.0));
+        open.push(tile - vec2(0.0, size.y));
+        open.push(tile + vec2(0.0, size.y));
+    }
+    true
+}
+
+struct Illusion {
+    position: Vec2,
+    hidden: bool,
+    revealed_id: u32,
+    /// Ticks the reveal effect has run, it starts once the logic tile shows the revealed id.
+    reveal_ticks: u32,
+}
+
+/// The reveal effect of the illusion tiles of a level: fades fake walls out and hidden platforms in.
+pub struct Illusions {
+    tiles: Vec<Illusion>,
+    draw_layers: Vec<LayerId>,
+}
+
+impl Illusions {
+    /// Illusion tiles without a `reveals_as` id stay as they are.
+    pub fn new(tilemap: &mut Tilemap, layers: &MapLayers, properties: &TileProperties) -> Illusions {
+        let draw_layers: Vec<LayerId> = tilemap
+            .layer_ids()
+            .into_iter()
+            .filter(|layer| *layer != layers.logic && *layer != layers.collision)
+            .collect();
+        let mut tiles = vec![];
+        for position in properties.find(tilemap, layers.logic, |p| p.fake_wall || p.hidden_platform) {
+            let property = properties.logic(tilemap.get_id_at_position(layers.logic, position)).unwrap();
+            if let Some(revealed_id) = property.reveals_as {
+                tiles.push(Illusion {
+                    position,
+                    hidden: property.hidden_platform,
+                    revealed_id,
+                    reveal_ticks: 0,
+                });
+            }
+        }
+        let illusions = Illusions { tiles, draw_layers };
+        illusions.apply(tilemap);
+        illusions
+    }
+
+    pub fn update(&mut self, tilemap: &mut Tilemap, layers: &MapLayers) {
+        let mut changed = false;
+        for tile in self.tiles.iter_mut() {
+            if tile.reveal_ticks < REVEAL_TICKS && tilemap.get_id_at_position(layers.logic, tile.position) == Some(tile.revealed_id) {
+                tile.reveal_ticks += 1;
+                changed = true;
+            }
+        }
+        if changed {
+            self.apply(tilemap);
+        }
+    }
+
+    fn apply(&self, tilemap: &mut Tilemap) {
+        for tile in self.tiles.iter() {
+            let progress = tile.reveal_ticks as f32 / REVEAL_TICKS as f32;
+            let alpha = if tile.hidden {
+                progress
+            } else {
+                1.0 - progress * (1.0 - REVEALED_WALL_ALPHA)
+            };
+            for layer in self.draw_layers.iter() {
+                tilemap.set_alpha_at(*layer, alpha, tile.position);
+            }
+        }
+    }
+}
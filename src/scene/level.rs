@@ -0,0 +1,247 @@
+use crate::assets;
+use crate::assets::AssetError;
+use crate::entity::physics::PhysicsOverride;
+use crate::scene::illusion::Illusions;
+use crate::scene::tile_properties::TileProperties;
+use crate::tilemap::error::TilemapError;
+use crate::tilemap::{LayerId, Tilemap};
+use macroquad::prelude::*;
+use nanoserde::DeJson;
+
+const MANIFEST: &str = "maps/levels.json";
+const MAPS_DIR: &str = "maps";
+const DEFAULT_PROPERTIES: &str = "tiles.json";
+
+#[derive(Clone, Debug, Default, DeJson)]
+struct LevelManifest {
+    levels: Vec<LevelInfo>,
+}
+
+#[derive(Clone, Debug, Default, DeJson)]
+pub struct LevelInfo {
+    pub name: String,
+    /// File name of the map, `.tmx`/`.tmj` maps are read as Tiled, `.json` as pyxeledit.
+    pub map: String,
+    pub tileset: String,
+    /// Images of the further tilesets of a Tiled map, in the order the map lists its tilesets.
+    #[nserde(default)]
+    pub extra_tilesets: Vec<String>,
+    /// File name of the tile property table, `tiles.json` if left out.
+    #[nserde(default)]
+    pub properties: String,
+    #[nserde(default)]
+    pub physics: String,
+}
+
+impl LevelInfo {
+    pub fn map_path(&self) -> String {
+        format!("{}/{}", MAPS_DIR, self.map)
+    }
+
+    pub fn tileset_path(&self) -> String {
+        format!("{}/{}", MAPS_DIR, self.tileset)
+    }
+
+    pub fn extra_tileset_paths(&self) -> Vec<String> {
+        self.extra_tilesets.iter().map(|tileset| format!("{}/{}", MAPS_DIR, tileset)).collect()
+    }
+
+    pub fn properties_path(&self) -> String {
+        if self.properties.is_empty() {
+            format!("{}/{}", MAPS_DIR, DEFAULT_PROPERTIES)
+        } else {
+            format!("{}/{}", MAPS_DIR, self.properties)
+        }
+    }
+
+    pub fn physics_path(&self) -> Option<String> {
+        if self.physics.is_empty() {
+            None
+        } else {
+            Some(format!("{}/{}", MAPS_DIR, self.physics))
+        }
+    }
+}
+
+/// The raw files of a level, kept around so levels can be switched without waiting on io.
+pub struct LevelSource {
+    pub info: LevelInfo,
+    pub map: Vec<u8>,
+    pub tileset: Vec<u8>,
+    pub extra_tilesets: Vec<Vec<u8>>,
+    pub properties: Vec<u8>,
+    /// Empty if the level keeps the common tunables.
+    pub physics: Vec<u8>,
+}
+
+/// Levels with missing files are reported and left out.
+pub async fn load_levels() -> Result<Vec<LevelSource>, TilemapError> {
+    let manifest = assets::load_string(MANIFEST).await?;
+    let manifest: LevelManifest =
+        DeJson::deserialize_json(&manifest).map_err(|e| TilemapError::Json(format!("{:?}", e)))?;
+    let mut levels = Vec::with_capacity(manifest.levels.len());
+    for info in manifest.levels {
+        let map = assets::load_bytes(&info.map_path()).await;
+        let tileset = assets::load_bytes(&info.tileset_path()).await;
+        let extra_tilesets = load_all(&info.extra_tileset_paths()).await;
+        let properties = assets::load_bytes(&info.properties_path()).await;
+        let physics = match info.physics_path() {
+            Some(path) => assets::load_bytes(&path).await,
+            None => Ok(vec![]),
+        };
+        match (map, tileset, extra_tilesets, properties, physics) {
+            (Ok(map), Ok(tileset), Ok(extra_tilesets), Ok(properties), Ok(physics)) => levels.push(LevelSource {
+                info,
+                map,
+                tileset,
+                extra_tilesets,
+                properties,
+                physics,
+            }),
+            (Err(e), _, _, _, _) | (_, Err(e), _, _, _) | (_, _, Err(e), _, _) | (_, _, _, Err(e), _) | (_, _, _, _, Err(e)) => {
+                error!("skipping level '{}': {}", info.name, e)
+            }
+        }
+    }
+    Ok(levels)
+}
+
+async fn load_all(paths: &[String]) -> Result<Vec<Vec<u8>>, AssetError> {
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        files.push(assets::load_bytes(path).await?);
+    }
+    Ok(files)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MapLayers {
+    pub logic: LayerId,
+    pub collision: LayerId,
+}
+
+impl MapLayers {
+    pub fn resolve(tilemap: &Tilemap) -> Result<MapLayers, TilemapError> {
+        Ok(MapLayers {
+            logic: tilemap.require_layer_id("logic")?,
+            collision: tilemap.require_layer_id("collision")?,
+        })
+    }
+}
+
+/// The physics override of `source`, one that can not be read is reported and left out
+/// like one with values the player can not move with.
+fn physics_override(source: &LevelSource) -> PhysicsOverride {
+    if source.physics.is_empty() {
+        return PhysicsOverride::default();
+    }
+    let json = String::from_utf8_lossy(&source.physics);
+    PhysicsOverride::from_json(&json).unwrap_or_else(|e| {
+        error!("ignoring the physics of level '{}': {}", source.info.name, e);
+        PhysicsOverride::default()
+    })
+}
+
+pub struct Level {
+    pub tilemap: Tilemap,
+    pub textures: Vec<Texture2D>,
+    pub layers: MapLayers,
+    pub properties: TileProperties,
+    pub illusions: Illusions,
+    pub physics: PhysicsOverride,
+    /// Area of the first tileset image, kept to rebuild the tilemap.
+    clip: Rect,
+}
+
+impl Level {
+    pub fn load(source: &LevelSource) -> Result<Level, TilemapError> {
+        let textures: Vec<Texture2D> = std::iter::once(&source.tileset)
+            .chain(source.extra_tilesets.iter())
+            .map(|tileset| assets::texture_from_bytes(tileset))
+            .collect();
+        let clip = Rect::new(0.0, 0.0, textures[0].width(), textures[0].height());
+        let level = Level::with_textures(source, textures.clone(), clip);
+        if level.is_err() {
+            textures.iter().for_each(Texture2D::delete);
+        }
+        level
+    }
+
+    /// Builds a level drawn with `textures`, `clip` is the area of the first one.
+    /// Without textures the level works headless and draws nothing.
+    pub fn with_textures(source: &LevelSource, textures: Vec<Texture2D>, clip: Rect) -> Result<Level, TilemapError> {
+        let physics = physics_override(source);
+        let (mut tilemap, layers, properties) = load_tilemap(source, clip)?;
+        let illusions = Illusions::new(&mut tilemap, &layers, &properties);
+        Ok(Level {
+            tilemap,
+            textures,
+            layers,
+            properties,
+            illusions,
+            physics,
+            clip,
+        })
+    }
+
+    /// Puts every tile back the way the map of `source` has it, so illusions revealed
+    /// in an earlier attempt are hidden again. The textures are kept.
+    pub fn restart(&mut self, source: &LevelSource) -> Result<(), TilemapError> {
+        let (mut tilemap, layers, properties) = load_tilemap(source, self.clip)?;
+        self.illusions = Illusions::new(&mut tilemap, &layers, &properties);
+        self.tilemap = tilemap;
+        self.layers = layers;
+        self.properties = properties;
+        Ok(())
+    }
+
+    pub fn unload(&self) {
+        self.textures.iter().for_each(Texture2D::delete);
+    }
+}
+
+/// `clip` is the area of the tileset image.
+pub fn load_tilemap(source: &LevelSource, clip: Rect) -> Result<(Tilemap, MapLayers, TileProperties), TilemapError> {
+    let data = String::from_utf8(source.map.clone())?;
+    let mut tilemap = if source.info.map.ends_with(".tmx") {
+        Tilemap::from_tmx(clip, &data)?
+    } else if source.info.map.ends_with(".tmj") {
+        Tilemap::from_tiled(clip, &data)?
+    } else {
+        Tilemap::from_pyxeledit(clip, &data)?
+    };
+    // the first tileset is always given, a map without tilesets draws from the clip of its image
+    if tilemap.tileset_count() > 1 + source.extra_tilesets.len() {
+        return Err(TilemapError::MissingTileset(1 + source.extra_tilesets.len()));
+    }
+    let layers = MapLayers::resolve(&tilemap)?;
+    let properties = TileProperties::from_json(&String::from_utf8(source.properties.clone())?)?;
+    if properties.find(&tilemap, layers.logic, |p| p.spawn).is_empty() {
+        return Err(TilemapError::MissingTile {
+            layer: "logic".to_string(),
+            property: "spawn",
+        });
+    }
+    tilemap.visibility(layers.logic, false);
+    tilemap.visibility(layers.collision, false);
+    Ok((tilemap, layers, properties))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_file_of_the_levels_is_embedded() {
+        let manifest = assets::embedded(MANIFEST).expect("the manifest is not embedded");
+        let manifest = LevelManifest::deserialize_json(std::str::from_utf8(manifest).unwrap()).unwrap();
+        for info in manifest.levels.iter() {
+            let mut paths = vec![info.map_path(), info.tileset_path(), info.properties_path()];
+            paths.extend(info.extra_tileset_paths());
+            paths.extend(info.physics_path());
+            for path in paths {
+                assert!(assets::embedded(&path).is_some(), "level '{}' needs '{}'", info.name, path);
+            }
+        }
+    }
+}
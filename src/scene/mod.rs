@@ -1,3 +1,9 @@
-pub(crate) mod end;
-pub(crate) mod game;
-pub(crate) mod title;
+pub mod controls;
+pub mod end;
+pub mod game;
+pub mod illusion;
+pub mod level;
+pub mod pause;
+pub mod settings;
+pub mod tile_properties;
+pub mod title;
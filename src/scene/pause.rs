@@ -0,0 +1,109 @@
+use crate::assets;
+use crate::input::{Action, Controls};
+use crate::scene::game::Game;
+use crate::storage::save::SaveGame;
+use crate::{MainState, FONT_COLOR};
+use macroquad::prelude::*;
+
+const OVERLAY_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum PauseEntry {
+    Resume,
+    RestartLevel,
+    Settings,
+    QuitToTitle,
+}
+
+impl PauseEntry {
+    fn label(&self) -> &'static str {
+        match self {
+            PauseEntry::Resume => "Resume",
+            PauseEntry::RestartLevel => "Restart level",
+            PauseEntry::Settings => "Settings",
+            PauseEntry::QuitToTitle => "Quit to title",
+        }
+    }
+}
+
+/// Menu drawn over the frozen game, nothing in `Game` advances while it is open.
+pub struct Pause {
+    font: Font,
+    entries: Vec<PauseEntry>,
+    selected: usize,
+}
+
+impl Pause {
+    pub async fn init() -> Pause {
+        let font = assets::load_font("fonts/Born2bSportyAkan.ttf").await.unwrap();
+        Pause {
+            font,
+            entries: vec![],
+            selected: 0,
+        }
+    }
+
+    /// Opens the menu with resume selected, a replay can not restart its level.
+    pub fn open(&mut self, game: &Game) {
+        self.entries = if game.is_replay() {
+            vec![PauseEntry::Resume, PauseEntry::Settings, PauseEntry::QuitToTitle]
+        } else {
+            vec![PauseEntry::Resume, PauseEntry::RestartLevel, PauseEntry::Settings, PauseEntry::QuitToTitle]
+        };
+        self.selected = 0;
+    }
+
+    pub fn run(&mut self, game: &mut Game, save: &mut SaveGame, controls: &Controls) -> Option<MainState> {
+        game.draw();
+        set_default_camera();
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), OVERLAY_COLOR);
+        self.draw_menu();
+
+        if controls.is_pressed(Action::Pause) || controls.is_pressed(Action::Back) {
+            return Some(MainState::GAME);
+        }
+        if controls.is_pressed(Action::Confirm) {
+            return match self.entries[self.selected] {
+                PauseEntry::Resume => Some(MainState::GAME),
+                PauseEntry::RestartLevel => {
+                    game.restart_level(save);
+                    Some(MainState::GAME)
+                }
+                PauseEntry::Settings => Some(MainState::SETTINGS),
+                PauseEntry::QuitToTitle => {
+                    game.persist(save);
+                    Some(MainState::TITLE)
+                }
+            };
+        }
+        if controls.is_pressed(Action::Jump) {
+            self.selected = (self.selected + self.entries.len() - 1) % self.entries.len();
+        } else if controls.is_pressed(Action::Duck) {
+            self.selected = (self.selected + 1) % self.entries.len();
+        }
+        None
+    }
+
+    fn draw_menu(&self) {
+        let font_size = 80;
+        let font_scale = 0.5;
+        let top = screen_height() / 2.0 - 40.0 * self.entries.len() as f32;
+        self.draw_centered("Paused", top, font_size, 0.7, FONT_COLOR);
+        for (i, entry) in self.entries.iter().enumerate() {
+            let color = if i == self.selected { FONT_COLOR } else { DARKGRAY };
+            self.draw_centered(entry.label(), top + 90.0 + i as f32 * 50.0, font_size, font_scale, color);
+        }
+    }
+
+    fn draw_centered(&self, text: &str, y: f32, font_size: u16, font_scale: f32, color: Color) {
+        let tp = TextParams {
+            font: self.font,
+            font_size,
+            font_scale,
+            font_scale_aspect: 1.0,
+            color,
+        };
+        let width = measure_text(text, Some(self.font), font_size, font_scale).width;
+        draw_text_ex(text, (screen_width() - width) / 2.0, y, tp);
+    }
+}
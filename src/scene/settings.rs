@@ -0,0 +1,160 @@
+use crate::assets;
+use crate::input::{Action, Controls};
+use crate::storage::settings::Settings;
+use crate::{MainState, FONT_COLOR};
+use macroquad::prelude::*;
+
+const FONT_SIZE: u16 = 80;
+const FONT_SCALE: f32 = 0.4;
+const LINE_HEIGHT: f32 = 44.0;
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum SettingsEntry {
+    MusicVolume,
+    SfxVolume,
+    Fullscreen,
+    PixelScale,
+    Controls,
+    Back,
+}
+
+impl SettingsEntry {
+    const ALL: [SettingsEntry; 6] = [
+        SettingsEntry::MusicVolume,
+        SettingsEntry::SfxVolume,
+        SettingsEntry::Fullscreen,
+        SettingsEntry::PixelScale,
+        SettingsEntry::Controls,
+        SettingsEntry::Back,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            SettingsEntry::MusicVolume => "Music volume",
+            SettingsEntry::SfxVolume => "Sound volume",
+            SettingsEntry::Fullscreen => "Fullscreen",
+            SettingsEntry::PixelScale => "Pixel scale",
+            SettingsEntry::Controls => "Controls",
+            SettingsEntry::Back => "Back",
+        }
+    }
+}
+
+pub struct SettingsMenu {
+    font: Font,
+    selected: usize,
+    back_to: MainState,
+    /// Fullscreen as it was when the window was created, it only changes on restart.
+    started_fullscreen: bool,
+}
+
+impl SettingsMenu {
+    pub async fn init(settings: &Settings) -> SettingsMenu {
+        let font = assets::load_font("fonts/Born2bSportyAkan.ttf").await.unwrap();
+        SettingsMenu {
+            font,
+            selected: 0,
+            back_to: MainState::TITLE,
+            started_fullscreen: settings.fullscreen,
+        }
+    }
+
+    pub fn open(&mut self, back_to: MainState) {
+        self.selected = 0;
+        self.back_to = back_to;
+    }
+
+    /// Changes are written when the menu is left.
+    pub fn run(&mut self, settings: &mut Settings, controls: &Controls) -> Option<MainState> {
+        set_default_camera();
+        let state = self.process_action(settings, controls);
+        self.draw(settings);
+        state
+    }
+
+    fn process_action(&mut self, settings: &mut Settings, controls: &Controls) -> Option<MainState> {
+        let entry = SettingsEntry::ALL[self.selected];
+        if controls.is_pressed(Action::Back) {
+            settings.write();
+            return Some(self.back_to.clone());
+        }
+        if controls.is_pressed(Action::Confirm) {
+            match entry {
+                SettingsEntry::Fullscreen => settings.fullscreen = !settings.fullscreen,
+                SettingsEntry::Controls => return Some(MainState::CONTROLS),
+                SettingsEntry::Back => {
+                    settings.write();
+                    return Some(self.back_to.clone());
+                }
+                _ => {}
+            }
+        } else if controls.is_pressed(Action::MoveLeft) {
+            change(settings, entry, -1);
+        } else if controls.is_pressed(Action::MoveRight) {
+            change(settings, entry, 1);
+        } else if controls.is_pressed(Action::Jump) {
+            let count = SettingsEntry::ALL.len();
+            self.selected = (self.selected + count - 1) % count;
+        } else if controls.is_pressed(Action::Duck) {
+            self.selected = (self.selected + 1) % SettingsEntry::ALL.len();
+        }
+        None
+    }
+
+    fn draw(&self, settings: &Settings) {
+        let x = screen_width() / 2.0 - 240.0;
+        let mut y = screen_height() / 2.0 - LINE_HEIGHT * (SettingsEntry::ALL.len() as f32 / 2.0 + 1.0);
+        self.draw_line("Settings", x, y, FONT_COLOR);
+        y += LINE_HEIGHT * 1.5;
+        for (i, entry) in SettingsEntry::ALL.iter().enumerate() {
+            let color = if i == self.selected { FONT_COLOR } else { DARKGRAY };
+            self.draw_line(entry.label(), x, y, color);
+            self.draw_line(&self.value(settings, *entry), x + 280.0, y, color);
+            y += LINE_HEIGHT;
+        }
+    }
+
+    fn value(&self, settings: &Settings, entry: SettingsEntry) -> String {
+        match entry {
+            SettingsEntry::MusicVolume => volume_bar(settings.music_volume),
+            SettingsEntry::SfxVolume => volume_bar(settings.sfx_volume),
+            SettingsEntry::Fullscreen => {
+                let value = if settings.fullscreen { "on" } else { "off" };
+                if settings.fullscreen != self.started_fullscreen {
+                    format!("{} (on restart)", value)
+                } else {
+                    value.to_string()
+                }
+            }
+            SettingsEntry::PixelScale => format!("< {}x >", settings.pixel_scale),
+            SettingsEntry::Controls | SettingsEntry::Back => String::new(),
+        }
+    }
+
+    fn draw_line(&self, text: &str, x: f32, y: f32, color: Color) {
+        let tp = TextParams {
+            font: self.font,
+            font_size: FONT_SIZE,
+            font_scale: FONT_SCALE,
+            font_scale_aspect: 1.0,
+            color,
+        };
+        draw_text_ex(text, x, y, tp);
+    }
+}
+
+fn change(settings: &mut Settings, entry: SettingsEntry, direction: i32) {
+    match entry {
+        SettingsEntry::MusicVolume => Settings::change_volume(&mut settings.music_volume, VOLUME_STEP * direction as f32),
+        SettingsEntry::SfxVolume => Settings::change_volume(&mut settings.sfx_volume, VOLUME_STEP * direction as f32),
+        SettingsEntry::Fullscreen => settings.fullscreen = !settings.fullscreen,
+        SettingsEntry::PixelScale => settings.change_pixel_scale(direction),
+        SettingsEntry::Controls | SettingsEntry::Back => {}
+    }
+}
+
+fn volume_bar(volume: f32) -> String {
+    let filled = (volume * 10.0).round() as usize;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(10 - filled.min(10)))
+}
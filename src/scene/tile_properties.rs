@@ -0,0 +1,192 @@
+use crate::tilemap::error::TilemapError;
+use crate::tilemap::{LayerId, Tilemap};
+use macroquad::prelude::*;
+use nanoserde::DeJson;
+use std::collections::HashMap;
+
+/// Every flag defaults to off, so an entry only lists what applies.
+#[derive(Clone, Debug, Default, PartialEq, DeJson)]
+pub struct TileProperty {
+    pub id: u32,
+    /// Whether the tile blocks the player. Collision tiles do unless they are one-way
+    /// or set it to false, logic tiles never do.
+    #[nserde(default)]
+    pub solid: Option<bool>,
+    #[nserde(default)]
+    pub deadly: bool,
+    #[nserde(default)]
+    pub exit: bool,
+    #[nserde(default)]
+    pub spawn: bool,
+    #[nserde(default)]
+    pub checkpoint: bool,
+    #[nserde(default)]
+    pub one_way: bool,
+    #[nserde(default)]
+    pub fake_wall: bool,
+    #[nserde(default)]
+    pub hidden_platform: bool,
+    /// Id an illusion tile is swapped for once the player found it.
+    #[nserde(default)]
+    pub reveals_as: Option<u32>,
+    /// How quickly the player comes to a stop on the tile, 1 is normal ground and less is slippery.
+    #[nserde(default)]
+    pub friction: Option<f32>,
+    /// Outline of a solid collision tile, one of the `TileShape` names, a full block if left out.
+    #[nserde(default)]
+    pub shape: String,
+}
+
+/// Outline of a solid collision tile, the part below the outline blocks the player from every side.
+/// Outlines low enough at the side the player walks in from are stepped onto, which is how slopes are walked up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TileShape {
+    Full,
+    Half,
+    SlopeUp,
+    SlopeDown,
+    /// Lower and upper tile of a 22.5° slope rising to the right, placed side by side.
+    SlopeUpLow,
+    SlopeUpHigh,
+    /// Upper and lower tile of a 22.5° slope falling to the right, placed side by side.
+    SlopeDownHigh,
+    SlopeDownLow,
+}
+
+impl TileShape {
+    fn from_name(name: &str) -> Option<TileShape> {
+        match name {
+            "" | "full" => Some(TileShape::Full),
+            "half" => Some(TileShape::Half),
+            "slope_up" => Some(TileShape::SlopeUp),
+            "slope_down" => Some(TileShape::SlopeDown),
+            "slope_up_low" => Some(TileShape::SlopeUpLow),
+            "slope_up_high" => Some(TileShape::SlopeUpHigh),
+            "slope_down_high" => Some(TileShape::SlopeDownHigh),
+            "slope_down_low" => Some(TileShape::SlopeDownLow),
+            _ => None,
+        }
+    }
+
+    fn heights(self) -> (f32, f32) {
+        match self {
+            TileShape::Full => (1.0, 1.0),
+            TileShape::Half => (0.5, 0.5),
+            TileShape::SlopeUp => (0.0, 1.0),
+            TileShape::SlopeDown => (1.0, 0.0),
+            TileShape::SlopeUpLow => (0.0, 0.5),
+            TileShape::SlopeUpHigh => (0.5, 1.0),
+            TileShape::SlopeDownHigh => (1.0, 0.5),
+            TileShape::SlopeDownLow => (0.5, 0.0),
+        }
+    }
+
+    /// Height of the surface `x` parts of the tile width from the left edge, in parts of the tile height.
+    pub fn height_at(self, x: f32) -> f32 {
+        let (left, right) = self.heights();
+        left + (right - left) * x.clamp(0.0, 1.0)
+    }
+
+    /// How much the surface rises from the left to the right edge, in parts of the tile height.
+    pub fn rise(self) -> f32 {
+        let (left, right) = self.heights();
+        right - left
+    }
+}
+
+#[derive(Clone, Debug, Default, DeJson)]
+struct PropertiesFile {
+    #[nserde(default)]
+    logic: Vec<TileProperty>,
+    #[nserde(default)]
+    collision: Vec<TileProperty>,
+}
+
+/// Tiles of the collision layer without an entry are plain solid ground.
+#[derive(Clone, Debug, Default)]
+pub struct TileProperties {
+    logic: HashMap<u32, TileProperty>,
+    collision: HashMap<u32, TileProperty>,
+    shapes: HashMap<u32, TileShape>,
+}
+
+impl TileProperties {
+    pub fn from_json(data: &str) -> Result<TileProperties, TilemapError> {
+        let mut file: PropertiesFile =
+            DeJson::deserialize_json(data).map_err(|e| TilemapError::Properties(format!("{:?}", e)))?;
+        let mut shapes = HashMap::new();
+        for property in file.collision.iter_mut() {
+            property.solid = Some(property.solid.unwrap_or(!property.one_way));
+            match TileShape::from_name(&property.shape) {
+                Some(shape) => shapes.insert(property.id, shape),
+                None => {
+                    return Err(TilemapError::Properties(format!(
+                        "unknown shape '{}' of collision tile {}",
+                        property.shape, property.id
+                    )))
+                }
+            };
+        }
+        Ok(TileProperties {
+            logic: file.logic.into_iter().map(|p| (p.id, p)).collect(),
+            collision: file.collision.into_iter().map(|p| (p.id, p)).collect(),
+            shapes,
+        })
+    }
+
+    pub fn logic(&self, id: Option<u32>) -> Option<&TileProperty> {
+        self.logic.get(&id?)
+    }
+
+    /// Properties of a collision tile, `None` for empty tiles. Ids without an entry are plain solid blocks.
+    pub fn collision(&self, id: Option<u32>) -> Option<TileProperty> {
+        let id = id?;
+        Some(self.collision.get(&id).cloned().unwrap_or(TileProperty {
+            id,
+            solid: Some(true),
+            ..Default::default()
+        }))
+    }
+
+    pub fn logic_at(&self, tilemap: &Tilemap, logic: LayerId, position: Vec2, has: fn(&TileProperty) -> bool) -> bool {
+        match self.logic(tilemap.get_id_at_position(logic, position)) {
+            Some(property) => has(property),
+            None => false,
+        }
+    }
+
+    pub fn solid_at(&self, tilemap: &Tilemap, collision: LayerId, position: Vec2) -> bool {
+        match self.collision(tilemap.get_id_at_position(collision, position)) {
+            Some(property) => property.solid == Some(true),
+            None => false,
+        }
+    }
+
+    /// Outline of the collision tile at `position`, `None` unless the tile is solid.
+    pub fn shape_at(&self, tilemap: &Tilemap, collision: LayerId, position: Vec2) -> Option<TileShape> {
+        let id = tilemap.get_id_at_position(collision, position)?;
+        match self.collision(Some(id)) {
+            Some(property) if property.solid == Some(true) => Some(self.shapes.get(&id).copied().unwrap_or(TileShape::Full)),
+            _ => None,
+        }
+    }
+
+    pub fn one_way_at(&self, tilemap: &Tilemap, collision: LayerId, position: Vec2) -> bool {
+        match self.collision(tilemap.get_id_at_position(collision, position)) {
+            Some(property) => property.one_way,
+            None => false,
+        }
+    }
+
+    pub fn friction_at(&self, tilemap: &Tilemap, collision: LayerId, position: Vec2) -> f32 {
+        self.collision(tilemap.get_id_at_position(collision, position))
+            .and_then(|p| p.friction)
+            .unwrap_or(1.0)
+    }
+
+    pub fn find(&self, tilemap: &Tilemap, logic: LayerId, has: fn(&TileProperty) -> bool) -> Vec<Vec2> {
+        let mut ids: Vec<u32> = self.logic.values().filter(|p| has(p)).map(|p| p.id).collect();
+        ids.sort_unstable();
+        ids.into_iter().flat_map(|id| tilemap.get_all_position_from_id(logic, id)).collect()
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{MainState, TITLE_ZOOM};
+use crate::assets;
+use crate::input::{Action, Controls};
+use crate::storage::save::SaveGame;
+use crate::storage::settings::Settings;
+use crate::{MainState, FONT_COLOR};
 use keyframe::functions::{EaseIn, EaseInOut, EaseOut, Linear};
 use keyframe::Keyframe;
 use macroquad::prelude::*;
@@ -8,53 +12,78 @@ use std::time::Duration;
 use crate::tilemap::Tilemap;
 use crate::utils::tween::Tween;
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum MenuEntry {
+    Continue,
+    NewGame,
+    Settings,
+}
+
+impl MenuEntry {
+    fn label(&self) -> &'static str {
+        match self {
+            MenuEntry::Continue => "Continue",
+            MenuEntry::NewGame => "New Game",
+            MenuEntry::Settings => "Settings",
+        }
+    }
+}
+
 pub struct Title {
     title: Texture2D,
+    font: Font,
+    entries: Vec<MenuEntry>,
+    selected: usize,
     camera: Camera2D,
     start: bool,
     player: TileAnim,
     spritesheet: Texture2D,
     game_name: Texture2D,
     animations: Vec<Tween>,
+    zoom: f32,
 }
 
 impl Title {
-    pub async fn init() -> Title {
+    pub async fn init(save: &SaveGame, settings: &Settings) -> Title {
         let camera = Camera2D {
-            zoom: vec2(TITLE_ZOOM / screen_width() * 2.0, -TITLE_ZOOM/ screen_height() * 2.0),
+            zoom: vec2(settings.zoom() / screen_width() * 2.0, -settings.zoom() / screen_height() * 2.0),
             target: vec2(67.0, 67.0),
             ..Default::default()
         };
-        let image = Image::from_file_with_format(include_bytes!("../../assets/images/title.png"), None);
-        let title: Texture2D = Texture2D::from_image(&image);
-        title.set_filter(FilterMode::Nearest);
+        let title = assets::load_texture("images/title.png").await.unwrap();
         let player_tilemap = Tilemap::new(Rect::new(0.0, 0.0, 64.0, 128.0), 8, 16, 8, 8);
         let player = TileAnim::new(&player_tilemap, &[16, 17, 18, 19], vec![
             Duration::from_millis(100),
             Duration::from_millis(200),
             Duration::from_millis(500),
             Duration::from_millis(400)]);
-        let spritesheet = get_player_spritesheet();
-        let game_name = get_name_texture();
+        let spritesheet = assets::load_texture("images/player.png").await.unwrap();
+        let game_name = assets::load_texture("images/name.png").await.unwrap();
         let animations = get_tween();
+        let font = assets::load_font("fonts/Born2bSportyAkan.ttf").await.unwrap();
         Title {
             camera,
             title,
+            font,
+            entries: menu_entries(save),
+            selected: 0,
             start: true,
             player,
             spritesheet,
             game_name,
-            animations
+            animations,
+            zoom: settings.zoom(),
         }
     }
 
-    pub fn run(&mut self) -> Option<MainState> {
+    pub fn run(&mut self, controls: &Controls) -> Option<MainState> {
 
         self.player.advance();
         self.animations[0].update();
         self.animations[1].update();
         self.animations[2].update();
 
+        self.camera.zoom = vec2(self.zoom / screen_width() * 2.0, -self.zoom / screen_height() * 2.0);
         set_camera(&self.camera);
         draw_texture_ex(self.title ,0.0, 0.0, WHITE, Default::default());
         draw_texture_ex(
@@ -68,7 +97,8 @@ impl Title {
             },
         );
         draw_texture_ex(self.game_name ,5.0, 10.0 + self.animations[0].value(), WHITE, Default::default());
-        match process_action(self){
+        draw_menu(self);
+        match process_action(self, controls){
             None => {None}
             Some(state) => {
                 //mixer.stop((self.sound_id.unwrap()));
@@ -76,35 +106,69 @@ impl Title {
             }
         }
     }
-    pub fn reset(&mut self){
+    pub fn reset(&mut self, save: &SaveGame){
         self.start = true;
+        self.entries = menu_entries(save);
+        self.selected = 0;
+    }
+    pub fn resume(&mut self, save: &SaveGame) {
+        self.entries = menu_entries(save);
+        self.selected = self.selected.min(self.entries.len() - 1);
+    }
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.zoom = settings.zoom();
+    }
+    pub fn continue_selected(&self) -> bool {
+        self.entries[self.selected] == MenuEntry::Continue
     }
 }
 
-fn process_action(_title: &mut Title) -> Option<MainState> {
-    if get_last_key_pressed().is_some() {
-        if is_key_pressed(KeyCode::Q) | is_key_pressed(KeyCode::Escape) {
-            #[cfg(not(target_arch = "wasm32"))]
-            return Some(MainState::EXIT);
-        } else {
-
-            return Some(MainState::GAME);
-        }
+fn menu_entries(save: &SaveGame) -> Vec<MenuEntry> {
+    if save.in_progress {
+        vec![MenuEntry::Continue, MenuEntry::NewGame, MenuEntry::Settings]
+    } else {
+        vec![MenuEntry::NewGame, MenuEntry::Settings]
     }
-    None
 }
 
-fn get_player_spritesheet() -> Texture2D {
-    let image = Image::from_file_with_format(include_bytes!("../../assets/images/player.png"), Some(ImageFormat::Png));
-    let texture: Texture2D = Texture2D::from_image(&image);
-    texture.set_filter(FilterMode::Nearest);
-    texture
+fn draw_menu(title: &Title) {
+    set_default_camera();
+    let font_size = 80;
+    let font_scale = 0.5;
+    for (i, entry) in title.entries.iter().enumerate() {
+        let color = if i == title.selected { FONT_COLOR } else { DARKGRAY };
+        let tp = TextParams {
+            font: title.font,
+            font_size,
+            font_scale,
+            font_scale_aspect: 1.0,
+            color,
+        };
+        let width = measure_text(entry.label(), Some(title.font), font_size, font_scale).width;
+        draw_text_ex(
+            entry.label(),
+            (screen_width() - width) / 2.0,
+            screen_height() - 120.0 + i as f32 * 50.0,
+            tp,
+        );
+    }
 }
-fn get_name_texture() -> Texture2D {
-    let image = Image::from_file_with_format(include_bytes!("../../assets/images/name.png"), Some(ImageFormat::Png));
-    let texture: Texture2D = Texture2D::from_image(&image);
-    texture.set_filter(FilterMode::Nearest);
-    texture
+
+fn process_action(title: &mut Title, controls: &Controls) -> Option<MainState> {
+    if controls.is_pressed(Action::Back) {
+        #[cfg(not(target_arch = "wasm32"))]
+        return Some(MainState::EXIT);
+    } else if controls.is_pressed(Action::Confirm) {
+        return match title.entries[title.selected] {
+            MenuEntry::Settings => Some(MainState::SETTINGS),
+            _ => Some(MainState::GAME),
+        };
+    } else if controls.is_pressed(Action::Jump) {
+        title.selected = (title.selected + title.entries.len() - 1) % title.entries.len();
+    } else if controls.is_pressed(Action::Duck) {
+        title.selected = (title.selected + 1) % title.entries.len();
+    }
+    None
 }
 
 fn get_tween() -> Vec<Tween>{
@@ -0,0 +1,33 @@
+pub mod save;
+pub mod settings;
+
+use std::path::PathBuf;
+
+const GAME_DIR: &str = "rustyjam21";
+
+/// Where files written by the game live, e.g. `~/.local/share/rustyjam21` on linux.
+/// Returns `None` when no data directory is known, wasm builds never have one.
+pub fn data_dir() -> Option<PathBuf> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let base = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))?;
+        Some(base.join(GAME_DIR))
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        None
+    }
+}
+
+pub fn read(name: &str) -> Option<String> {
+    std::fs::read_to_string(data_dir()?.join(name)).ok()
+}
+
+pub fn write(name: &str, contents: &str) -> std::io::Result<()> {
+    let dir = data_dir().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no data directory"))?;
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join(name), contents)
+}
@@ -0,0 +1,152 @@
+use crate::storage;
+use crate::utils::fixed_step::TICK_RATE;
+use macroquad::prelude::*;
+use nanoserde::{DeJson, SerJson};
+use std::collections::HashMap;
+
+const SAVE_FILE: &str = "save.json";
+/// Saves without a version keep their best times in seconds.
+const SAVE_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, DeJson, SerJson)]
+pub struct SavedPosition {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl From<Vec2> for SavedPosition {
+    fn from(v: Vec2) -> Self {
+        SavedPosition { x: v.x, y: v.y }
+    }
+}
+
+impl From<SavedPosition> for Vec2 {
+    fn from(p: SavedPosition) -> Self {
+        vec2(p.x, p.y)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, DeJson, SerJson)]
+pub struct SaveGame {
+    #[nserde(default)]
+    pub version: u32,
+    /// Whether there is a run to continue, cleared once the last level is finished.
+    pub in_progress: bool,
+    pub level: usize,
+    pub checkpoint: Option<SavedPosition>,
+    pub deaths: u32,
+    #[nserde(default)]
+    pub run_ticks: u32,
+    #[nserde(default)]
+    pub level_ticks: u32,
+    #[nserde(default)]
+    pub best_level_ticks: HashMap<String, u32>,
+    #[nserde(default)]
+    pub best_run_ticks: Option<u32>,
+}
+
+#[derive(DeJson)]
+struct UnversionedSave {
+    #[nserde(default)]
+    best_times: HashMap<String, f64>,
+}
+
+impl Default for SaveGame {
+    fn default() -> Self {
+        SaveGame {
+            version: SAVE_VERSION,
+            in_progress: false,
+            level: 0,
+            checkpoint: None,
+            deaths: 0,
+            run_ticks: 0,
+            level_ticks: 0,
+            best_level_ticks: HashMap::new(),
+            best_run_ticks: None,
+        }
+    }
+}
+
+impl SaveGame {
+    pub fn load() -> SaveGame {
+        match storage::read(SAVE_FILE) {
+            Some(json) => SaveGame::from_json(&json).unwrap_or_else(|e| {
+                error!("ignoring broken save file: {}", e);
+                SaveGame::default()
+            }),
+            None => SaveGame::default(),
+        }
+    }
+
+    /// Reads a save of any version, older ones are brought up to date.
+    pub fn from_json(json: &str) -> Result<SaveGame, String> {
+        let mut save = SaveGame::deserialize_json(json).map_err(|e| format!("{:?}", e))?;
+        if save.version == 0 {
+            let old = UnversionedSave::deserialize_json(json).map_err(|e| format!("{:?}", e))?;
+            for (level, seconds) in old.best_times {
+                let ticks = (seconds * TICK_RATE as f64).round() as u32;
+                save.best_level_ticks.entry(level).or_insert(ticks);
+            }
+        }
+        save.version = SAVE_VERSION;
+        Ok(save)
+    }
+
+    pub fn write(&self) {
+        if let Err(e) = storage::write(SAVE_FILE, &self.serialize_json()) {
+            error!("could not write save file: {}", e);
+        }
+    }
+
+    /// Starts a new run, best times are kept.
+    pub fn new_game(&mut self) {
+        self.in_progress = true;
+        self.level = 0;
+        self.checkpoint = None;
+        self.deaths = 0;
+        self.run_ticks = 0;
+        self.level_ticks = 0;
+    }
+
+    pub fn finish_game(&mut self) {
+        self.in_progress = false;
+        self.level = 0;
+        self.checkpoint = None;
+    }
+
+    pub fn record_level(&mut self, level: &str, ticks: u32) -> bool {
+        match self.best_level_ticks.get(level) {
+            Some(best) if *best <= ticks => false,
+            _ => {
+                self.best_level_ticks.insert(level.to_string(), ticks);
+                true
+            }
+        }
+    }
+
+    pub fn record_run(&mut self, ticks: u32) -> bool {
+        match self.best_run_ticks {
+            Some(best) if best <= ticks => false,
+            _ => {
+                self.best_run_ticks = Some(ticks);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_without_a_version_keep_their_best_times() {
+        let json = r#"{"in_progress":true,"level":1,"checkpoint":null,"deaths":3,"best_times":{"illusions":12.5},"settings":{"music_volume":0.6,"sfx_volume":1.0}}"#;
+        let save = SaveGame::from_json(json).unwrap();
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!((save.in_progress, save.level, save.deaths), (true, 1, 3));
+        assert_eq!(save.best_level_ticks.get("illusions"), Some(&(12 * TICK_RATE + TICK_RATE / 2)));
+        let save = SaveGame::from_json(&save.serialize_json()).unwrap();
+        assert_eq!(save.best_level_ticks.get("illusions"), Some(&(12 * TICK_RATE + TICK_RATE / 2)));
+    }
+}
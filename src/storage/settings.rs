@@ -0,0 +1,75 @@
+use crate::storage;
+use macroquad::prelude::*;
+use nanoserde::{DeJson, SerJson};
+
+const SETTINGS_FILE: &str = "settings.json";
+/// Width and height of the window at the default pixel scale, other scales keep the same view in game pixels.
+const DEFAULT_WINDOW_SIZE: i32 = 800;
+const DEFAULT_PIXEL_SCALE: u32 = 6;
+pub const MIN_PIXEL_SCALE: u32 = 2;
+pub const MAX_PIXEL_SCALE: u32 = 10;
+
+/// Read before the window is created, so the window size and fullscreen apply on the next start.
+#[derive(Clone, Debug, PartialEq, DeJson, SerJson)]
+pub struct Settings {
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub fullscreen: bool,
+    /// Screen pixels per game pixel.
+    pub pixel_scale: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            music_volume: 0.6,
+            sfx_volume: 1.0,
+            fullscreen: false,
+            pixel_scale: DEFAULT_PIXEL_SCALE,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        let mut settings = match storage::read(SETTINGS_FILE) {
+            Some(json) => Settings::deserialize_json(&json).unwrap_or_else(|e| {
+                error!("ignoring broken settings file: {:?}", e);
+                Settings::default()
+            }),
+            None => Settings::default(),
+        };
+        settings.clamp();
+        settings
+    }
+
+    pub fn write(&self) {
+        if let Err(e) = storage::write(SETTINGS_FILE, &self.serialize_json()) {
+            error!("could not write settings file: {}", e);
+        }
+    }
+
+    pub fn window_size(&self) -> (i32, i32) {
+        let size = DEFAULT_WINDOW_SIZE * self.pixel_scale as i32 / DEFAULT_PIXEL_SCALE as i32;
+        (size, size)
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.pixel_scale as f32
+    }
+
+    /// Changes a volume by `delta`, keeping it between 0 and 1 in steps of a tenth.
+    pub fn change_volume(volume: &mut f32, delta: f32) {
+        *volume = ((*volume + delta) * 10.0).round().clamp(0.0, 10.0) / 10.0;
+    }
+
+    pub fn change_pixel_scale(&mut self, delta: i32) {
+        self.pixel_scale = (self.pixel_scale as i32 + delta).clamp(MIN_PIXEL_SCALE as i32, MAX_PIXEL_SCALE as i32) as u32;
+    }
+
+    fn clamp(&mut self) {
+        self.music_volume = self.music_volume.clamp(0.0, 1.0);
+        self.sfx_volume = self.sfx_volume.clamp(0.0, 1.0);
+        self.pixel_scale = self.pixel_scale.clamp(MIN_PIXEL_SCALE, MAX_PIXEL_SCALE);
+    }
+}
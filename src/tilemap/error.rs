@@ -0,0 +1,65 @@
+use crate::assets::AssetError;
+use crate::tilemap::TILE_SIZE;
+use std::fmt;
+use std::string::FromUtf8Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TilemapError {
+    Asset(AssetError),
+    Encoding,
+    Json(String),
+    Tmx(String),
+    Properties(String),
+    MissingField(&'static str),
+    MissingLayer(String),
+    MissingTileset(usize),
+    UnsupportedExternalTileset(String),
+    MissingTile { layer: String, property: &'static str },
+    InvalidMapSize { width: i64, height: i64 },
+    UnsupportedTileSize { width: i32, height: i32 },
+    TileOutOfRange { layer: String, x: i32, y: i32 },
+    UnknownTileId { layer: String, id: u32 },
+}
+
+impl fmt::Display for TilemapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TilemapError::Asset(e) => write!(f, "{}", e),
+            TilemapError::Encoding => write!(f, "map data is not valid utf-8"),
+            TilemapError::Json(msg) => write!(f, "invalid map json: {}", msg),
+            TilemapError::Tmx(msg) => write!(f, "invalid tmx map: {}", msg),
+            TilemapError::Properties(msg) => write!(f, "invalid tile properties: {}", msg),
+            TilemapError::MissingField(field) => write!(f, "map is missing the field '{}'", field),
+            TilemapError::MissingLayer(name) => write!(f, "map has no layer named '{}'", name),
+            TilemapError::MissingTileset(index) => write!(f, "map uses tileset {} but the level names no image for it", index),
+            TilemapError::MissingTile { layer, property } => write!(f, "map has no {} tile on layer '{}'", property, layer),
+            TilemapError::UnsupportedExternalTileset(source) => {
+                write!(f, "tileset '{}' is kept outside of the map, embed it in the map instead", source)
+            }
+            TilemapError::InvalidMapSize { width, height } => write!(f, "map size {}x{} is not positive", width, height),
+            TilemapError::UnsupportedTileSize { width, height } => {
+                write!(f, "tiles are {}x{} but only {}x{} tiles are supported", width, height, TILE_SIZE, TILE_SIZE)
+            }
+            TilemapError::TileOutOfRange { layer, x, y } => {
+                write!(f, "tile at {},{} on layer '{}' is outside of the map", x, y, layer)
+            }
+            TilemapError::UnknownTileId { layer, id } => {
+                write!(f, "tile id {} on layer '{}' is not part of the tileset", id, layer)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TilemapError {}
+
+impl From<FromUtf8Error> for TilemapError {
+    fn from(_: FromUtf8Error) -> Self {
+        TilemapError::Encoding
+    }
+}
+
+impl From<AssetError> for TilemapError {
+    fn from(e: AssetError) -> Self {
+        TilemapError::Asset(e)
+    }
+}
@@ -1,12 +1,19 @@
+pub(crate) mod error;
 mod pyxeledit;
+mod tiled;
 pub(crate) mod tile_animation;
 
+use crate::tilemap::error::TilemapError;
 use crate::tilemap::pyxeledit::PyxelTilemap;
+use crate::tilemap::tiled::TiledMap;
 use crate::utils::vecgrid::VecGrid;
 use crate::DEBUG;
 use macroquad::prelude::*;
 use std::collections::HashMap;
 
+/// Width and height of the tiles of a level map, the position lookups assume it.
+pub const TILE_SIZE: i32 = 8;
+
 #[allow(dead_code)]
 impl Tilemap {
     pub fn new(clip: Rect, tile_width: i32, tile_height: i32, width: usize, height: usize) -> Tilemap {
@@ -21,13 +28,31 @@ impl Tilemap {
                 ..Layer::default()
             }],
             tile_rectangles: get_tile_rectangles(clip, tile_width, tile_height),
+            tilesets: Vec::new(),
             layer_to_draw: DEFAULT_LAYER_TO_DRAW,
         }
     }
 
-    pub fn from_pyxeledit(clip: Rect, data: &str) -> Tilemap {
-        let pyxeltilemap = PyxelTilemap::new(data);
-        transform_pyxeltilemap(clip, pyxeltilemap)
+    pub fn from_pyxeledit(clip: Rect, data: &str) -> Result<Tilemap, TilemapError> {
+        let pyxeltilemap = PyxelTilemap::new(data)?;
+        let tilemap = transform_pyxeltilemap(clip, pyxeltilemap)?;
+        tilemap.check_tile_ids(tilemap.tile_rectangles.as_ref().unwrap())?;
+        Ok(tilemap)
+    }
+
+    /// `clip` describes the image of the first tileset.
+    pub fn from_tiled(clip: Rect, data: &str) -> Result<Tilemap, TilemapError> {
+        let tiledmap = TiledMap::from_json(data)?;
+        let tilemap = transform_tiledmap(clip, tiledmap)?;
+        tilemap.check_tile_ids(tilemap.tile_rectangles.as_ref().unwrap())?;
+        Ok(tilemap)
+    }
+
+    pub fn from_tmx(clip: Rect, data: &str) -> Result<Tilemap, TilemapError> {
+        let tiledmap = TiledMap::from_tmx(data)?;
+        let tilemap = transform_tiledmap(clip, tiledmap)?;
+        tilemap.check_tile_ids(tilemap.tile_rectangles.as_ref().unwrap())?;
+        Ok(tilemap)
     }
 
     pub fn color(&mut self, color: Color) -> &Tilemap {
@@ -48,10 +73,22 @@ impl Tilemap {
         }
     }
 
-    pub fn set_tile_rectangles_from(&mut self, texture2d: &Texture2D){
-        self.tile_rectangles = get_tile_rectangles(
-            Rect::new(0.0,0.0,texture2d.width(),texture2d.height()),
-            self.tile_width, self.tile_height);
+    fn check_tile_ids(&self, tile_rectangles: &HashMap<u32, Rect>) -> Result<(), TilemapError> {
+        for layer in self.layers.iter() {
+            for tile in layer.tiles.get_data().iter().flatten() {
+                let known = match self.tilesets.get(tile.tileset) {
+                    Some(tileset) => tile.id >= tileset.first_id && tile.id - tileset.first_id < tileset.tile_count,
+                    None => tile.tileset == 0 && tile_rectangles.contains_key(&tile.id),
+                };
+                if !known {
+                    return Err(TilemapError::UnknownTileId {
+                        layer: layer.name.clone(),
+                        id: tile.id,
+                    });
+                }
+            }
+        }
+        Ok(())
     }
 
     pub fn viewport(&mut self, rectangle: Rect) -> &Tilemap {
@@ -59,9 +96,9 @@ impl Tilemap {
         self
     }
 
-    pub fn get_all_position_from_id(&self, layer: usize, id: u32) -> Vec<Vec2> {
+    pub fn get_all_position_from_id(&self, layer: LayerId, id: u32) -> Vec<Vec2> {
         let mut positions = Vec::new();
-        if let Some(layer) = self.layers.get(layer) {
+        if let Some(layer) = self.layers.get(layer.0) {
             let tiles = &*layer.tiles.get_data();
             for (i, t) in tiles.iter().enumerate() {
                 if t.is_some() && t.as_ref().unwrap().id == id {
@@ -74,8 +111,8 @@ impl Tilemap {
         positions
     }
 
-    pub fn replace_all_tileid(&mut self, layer: usize, old_id: u32, new_id: Option<u32>) {
-        if let Some(layer) = self.layers.get_mut(layer) {
+    pub fn replace_all_tileid(&mut self, layer: LayerId, old_id: u32, new_id: Option<u32>) {
+        if let Some(layer) = self.layers.get_mut(layer.0) {
             for x in 0..self.width {
                 for y in 0..self.height {
                     if let Some(tile) = layer.tiles.get_mut(x, y) {
@@ -94,18 +131,18 @@ impl Tilemap {
         }
     }
 
-    pub fn set_tileid_at(&mut self, layer: usize, new_id: Option<u32>, position: Vec2) {
+    pub fn set_tileid_at(&mut self, layer: LayerId, new_id: Option<u32>, position: Vec2) {
         let mut pos_x = position.x as i32;
         let mut pos_y = position.y as i32;
-        if pos_x % 8 != 0 {
-            pos_x -= pos_x % 8;
+        if pos_x % TILE_SIZE != 0 {
+            pos_x -= pos_x % TILE_SIZE;
         }
-        if pos_y % 8 != 0 {
-            pos_y -= pos_y % 8;
+        if pos_y % TILE_SIZE != 0 {
+            pos_y -= pos_y % TILE_SIZE;
         }
         let x = pos_x as i32 / self.tile_width;
         let y = pos_y as i32 / self.tile_height;
-        if let Some(layer) = self.layers.get_mut(layer) {
+        if let Some(layer) = self.layers.get_mut(layer.0) {
             if new_id.is_some() {
                 match layer.tiles.get_mut(x as _, y as _) {
                     None => layer.tiles.set(
@@ -130,25 +167,41 @@ impl Tilemap {
         }
     }
 
-    pub fn visibility(&mut self, layer: usize, visibility: bool) {
-        if let Some(mut l) = self.layers.get_mut(layer) {
+    /// Makes the tile of `layer` at `position` see-through, 0 hides it and 1 draws it as usual.
+    pub fn set_alpha_at(&mut self, layer: LayerId, alpha: f32, position: Vec2) {
+        if !self.is_inside_map(position) {
+            return;
+        }
+        let x = position.x as i32 / self.tile_width;
+        let y = position.y as i32 / self.tile_height;
+        if let Some(tile) = self.layers.get_mut(layer.0).and_then(|l| l.tiles.get_mut(x as _, y as _)) {
+            tile.alpha = alpha;
+        }
+    }
+
+    pub fn visibility(&mut self, layer: LayerId, visibility: bool) {
+        if let Some(mut l) = self.layers.get_mut(layer.0) {
             l.visibility = visibility
         } else {
             //error!("layer{} not found!", layer);
         }
     }
 
-    pub fn get_layer_id(&self, name: &str) -> usize {
-        for (i, layer) in self.layers.iter().enumerate() {
-            if layer.name.eq(name) {
-                return i;
-            }
-        }
-        99
+    pub fn layer_ids(&self) -> Vec<LayerId> {
+        (0..self.layers.len()).map(LayerId).collect()
     }
 
-    pub fn get_layer_name(&self, layer: usize) -> &str {
-        if let Some(layer) = self.layers.get(layer as usize) {
+    pub fn get_layer_id(&self, name: &str) -> Option<LayerId> {
+        self.layers.iter().position(|layer| layer.name.eq(name)).map(LayerId)
+    }
+
+    pub fn require_layer_id(&self, name: &str) -> Result<LayerId, TilemapError> {
+        self.get_layer_id(name)
+            .ok_or_else(|| TilemapError::MissingLayer(name.to_string()))
+    }
+
+    pub fn get_layer_name(&self, layer: LayerId) -> &str {
+        if let Some(layer) = self.layers.get(layer.0) {
             &layer.name
         } else {
             //error!("layer{} not found!", layer);
@@ -156,22 +209,22 @@ impl Tilemap {
         }
     }
 
-    pub fn get_id_at_position(&self, layer: usize, position: Vec2) -> Option<u32> {
+    pub fn get_id_at_position(&self, layer: LayerId, position: Vec2) -> Option<u32> {
         let mut pos_x = position.x as i32;
         let mut pos_y = position.y as i32;
-        if pos_x % 8 != 0 {
-            pos_x -= pos_x % 8;
+        if pos_x % TILE_SIZE != 0 {
+            pos_x -= pos_x % TILE_SIZE;
         }
-        if pos_y % 8 != 0 {
-            pos_y -= pos_y % 8;
+        if pos_y % TILE_SIZE != 0 {
+            pos_y -= pos_y % TILE_SIZE;
         }
         let x = pos_x as i32 / self.tile_width;
         let y = pos_y as i32 / self.tile_height;
         self.get_id_at(layer, x as usize, y as usize)
     }
 
-    pub fn get_id_at(&self, layer_nr: usize, x: usize, y: usize) -> Option<u32> {
-        match self.layers.get(layer_nr) {
+    pub fn get_id_at(&self, layer: LayerId, x: usize, y: usize) -> Option<u32> {
+        match self.layers.get(layer.0) {
             None => None,
             Some(layer) => match layer.tiles.get(x, y) {
                 None => None,
@@ -180,6 +233,24 @@ impl Tilemap {
         }
     }
 
+    pub fn snap_to_tile(&self, position: Vec2) -> Vec2 {
+        vec2(
+            (position.x / self.tile_width as f32).floor() * self.tile_width as f32,
+            (position.y / self.tile_height as f32).floor() * self.tile_height as f32,
+        )
+    }
+
+    pub fn tile_size(&self) -> Vec2 {
+        vec2(self.tile_width as f32, self.tile_height as f32)
+    }
+
+    pub fn is_inside_map(&self, position: Vec2) -> bool {
+        position.x >= 0.0
+            && position.y >= 0.0
+            && position.x < (self.width as i32 * self.tile_width) as f32
+            && position.y < (self.height as i32 * self.tile_height) as f32
+    }
+
     fn is_inside_viewport(&self, position: Vec2) -> bool {
         !(position.x < self.viewport.x
             || position.y < self.viewport.y
@@ -191,6 +262,18 @@ impl Tilemap {
         self.tile_rectangles.as_ref().unwrap()[&id]
     }
 
+    /// How many tilesets the tiles are drawn from, 0 for maps that only use the clip of one image.
+    pub fn tileset_count(&self) -> usize {
+        self.tilesets.len()
+    }
+
+    fn get_tile_source(&self, tile: &Tile) -> Rect {
+        match self.tilesets.get(tile.tileset) {
+            Some(tileset) => tileset.source(tile.id, self.tile_width, self.tile_height),
+            None => self.get_rect_from_id(tile.id),
+        }
+    }
+
     pub fn get_frames_from_ids(&self, ids: &[u32]) -> Vec<Rect> {
         let mut frames = Vec::with_capacity(ids.len());
         for id in ids {
@@ -228,22 +311,31 @@ impl Tilemap {
         self.layers.push(layer);
     }
 
-    pub fn draw(&self, texture: Texture2D, position: Vec2, layer_to_draw: Option<usize>) {
+    /// Textures are given in tileset order, tiles of tilesets without one are skipped.
+    pub fn draw(&self, textures: &[Texture2D], position: Vec2, layer_to_draw: Option<LayerId>) {
         for (i, layer) in self.layers.iter().enumerate() {
-            if layer.visibility && layer_to_draw.is_none() || layer_to_draw.is_some() && i == layer_to_draw.unwrap() {
+            if layer.visibility && layer_to_draw.is_none() || layer_to_draw == Some(LayerId(i)) {
                 for tile in layer.tiles.get_data().iter().filter(|t| t.is_some()) {
                     match tile {
                         None => (),
                         Some(tile) => {
+                            let texture = match textures.get(tile.tileset) {
+                                None => continue,
+                                Some(texture) => *texture,
+                            };
+                            if tile.alpha <= 0.0 {
+                                continue;
+                            }
                             let tmp_pos = Vec2::new(position.x + tile.position_x, position.y + tile.position_y);
+                            let color = Color { a: layer.color.a * tile.alpha, ..layer.color };
                             draw_texture_ex(
                                 texture,
                                 tmp_pos.x,
                                 tmp_pos.y,
-                                layer.color,
+                                color,
                                 DrawTextureParams {
                                     dest_size: Some(tile.dest_size),
-                                    source: Some(self.get_rect_from_id(tile.id)),
+                                    source: Some(self.get_tile_source(tile)),
                                     rotation: tile.rotation,
                                     pivot: None,
                                     ..Default::default()
@@ -269,10 +361,38 @@ pub struct Tilemap {
     tile_width: i32,
     layers: Vec<Layer>,
     tile_rectangles: Option<HashMap<u32, Rect>>,
+    tilesets: Vec<Tileset>,
 
     layer_to_draw: i64,
 }
 
+#[derive(Debug, Clone)]
+struct Tileset {
+    origin: Vec2,
+    columns: u32,
+    tile_count: u32,
+    /// Id of the first tile, ids count on across the tilesets of a map so every tile has its own.
+    first_id: u32,
+}
+
+impl Tileset {
+    /// Tiled counts tile ids row by row.
+    fn source(&self, id: u32, tile_width: i32, tile_height: i32) -> Rect {
+        let index = id - self.first_id;
+        Rect::new(
+            self.origin.x + ((index % self.columns) as i32 * tile_width) as f32,
+            self.origin.y + ((index / self.columns) as i32 * tile_height) as f32,
+            tile_width as f32,
+            tile_height as f32,
+        )
+    }
+}
+
+/// Handle to a layer of one tilemap, look it up once with `Tilemap::get_layer_id`
+/// and keep it instead of the layer name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LayerId(usize);
+
 #[derive(Debug)]
 pub struct Layer {
     tiles: VecGrid<Tile>,
@@ -291,6 +411,8 @@ pub struct Tile {
     position_y: f32,
     rotation: f32,
     dest_size: Vec2,
+    tileset: usize,
+    alpha: f32,
 }
 
 fn get_tile_rectangles(clip: Rect, tile_width: i32, tile_height: i32) -> Option<HashMap<u32, Rect>> {
@@ -313,8 +435,27 @@ fn get_tile_rectangles(clip: Rect, tile_width: i32, tile_height: i32) -> Option<
     Some(tile_rectangles)
 }
 
-fn transform_pyxeltilemap(clip: Rect, pyxeltilemap: PyxelTilemap) -> Tilemap {
-    Tilemap {
+fn check_size(width: i64, height: i64, tile_width: i32, tile_height: i32) -> Result<(), TilemapError> {
+    if width <= 0 || height <= 0 {
+        return Err(TilemapError::InvalidMapSize { width, height });
+    }
+    if tile_width != TILE_SIZE || tile_height != TILE_SIZE {
+        return Err(TilemapError::UnsupportedTileSize {
+            width: tile_width,
+            height: tile_height,
+        });
+    }
+    Ok(())
+}
+
+fn transform_pyxeltilemap(clip: Rect, pyxeltilemap: PyxelTilemap) -> Result<Tilemap, TilemapError> {
+    check_size(
+        pyxeltilemap.tileswide,
+        pyxeltilemap.tileshigh,
+        pyxeltilemap.tile_width,
+        pyxeltilemap.tile_height,
+    )?;
+    Ok(Tilemap {
         width: pyxeltilemap.tileswide as usize,
         height: pyxeltilemap.tileshigh as usize,
         viewport: DEFAULT_RECTANGLE,
@@ -324,40 +465,157 @@ fn transform_pyxeltilemap(clip: Rect, pyxeltilemap: PyxelTilemap) -> Tilemap {
             &pyxeltilemap.layers,
             pyxeltilemap.tileswide as usize,
             pyxeltilemap.tileshigh as usize,
-        ),
+        )?,
         tile_rectangles: get_tile_rectangles(clip, pyxeltilemap.tile_width, pyxeltilemap.tile_height),
+        tilesets: Vec::new(),
         layer_to_draw: DEFAULT_LAYER_TO_DRAW,
-    }
+    })
 }
 
-fn transform_pyxellayer(pyxellayers: &[pyxeledit::Layers], width: usize, height: usize) -> Vec<Layer> {
+fn transform_pyxellayer(pyxellayers: &[pyxeledit::Layers], width: usize, height: usize) -> Result<Vec<Layer>, TilemapError> {
     let mut layers: Vec<Layer> = Vec::with_capacity(pyxellayers.len());
     for pyxellayer in pyxellayers.iter().rev() {
         let l = Layer {
-            tiles: transform_pyxeltile(&pyxellayer.tiles, width, height),
+            tiles: transform_pyxeltile(&pyxellayer.tiles, &pyxellayer.name, width, height)?,
             name: pyxellayer.name.clone(),
             ..Layer::default()
         };
         layers.push(l);
     }
-    layers
+    Ok(layers)
 }
 
-fn transform_pyxeltile(pyxeltiles: &[pyxeledit::Tile], width: usize, height: usize) -> VecGrid<Tile> {
+fn transform_pyxeltile(pyxeltiles: &[pyxeledit::Tile], layer: &str, width: usize, height: usize) -> Result<VecGrid<Tile>, TilemapError> {
     let mut vecgrid: VecGrid<Tile> = VecGrid::new(width, height);
     for t in pyxeltiles.iter() {
+        if t.x < 0 || t.y < 0 || t.x as usize >= width || t.y as usize >= height {
+            return Err(TilemapError::TileOutOfRange {
+                layer: layer.to_string(),
+                x: t.x,
+                y: t.y,
+            });
+        }
+        let dest_size = t.dest_size.ok_or(TilemapError::MissingField("dest_size"))?;
         let tile = Tile {
             id: t.id as u32,
             x: t.x,
             y: t.y,
-            position_x: t.position_x.unwrap(),
-            position_y: t.position_y.unwrap(),
-            rotation: t.rotation.unwrap(),
-            dest_size: vec2(t.dest_size.unwrap().0, t.dest_size.unwrap().1),
+            position_x: t.position_x.ok_or(TilemapError::MissingField("position_x"))?,
+            position_y: t.position_y.ok_or(TilemapError::MissingField("position_y"))?,
+            rotation: t.rotation.ok_or(TilemapError::MissingField("rotation"))?,
+            dest_size: vec2(dest_size.0, dest_size.1),
+            tileset: 0,
+            alpha: 1.0,
         };
         vecgrid.set(tile, t.x as usize, t.y as usize);
     }
-    vecgrid
+    Ok(vecgrid)
+}
+
+fn transform_tiledmap(clip: Rect, tiledmap: TiledMap) -> Result<Tilemap, TilemapError> {
+    check_size(
+        tiledmap.width as i64,
+        tiledmap.height as i64,
+        tiledmap.tile_width,
+        tiledmap.tile_height,
+    )?;
+    let tilesets = transform_tiledtilesets(clip, &tiledmap)?;
+    let tile_rectangles = tilesets.first().map(|tileset| {
+        (tileset.first_id..tileset.first_id + tileset.tile_count)
+            .map(|id| (id, tileset.source(id, tiledmap.tile_width, tiledmap.tile_height)))
+            .collect()
+    });
+    Ok(Tilemap {
+        width: tiledmap.width as usize,
+        height: tiledmap.height as usize,
+        viewport: DEFAULT_RECTANGLE,
+        tile_height: tiledmap.tile_height,
+        tile_width: tiledmap.tile_width,
+        layers: transform_tiledlayer(&tiledmap)?,
+        tile_rectangles: Some(tile_rectangles.unwrap_or_default()),
+        tilesets,
+        layer_to_draw: DEFAULT_LAYER_TO_DRAW,
+    })
+}
+
+/// The first tileset is drawn from `clip`, which also stands in for a size the map leaves out.
+fn transform_tiledtilesets(clip: Rect, tiledmap: &TiledMap) -> Result<Vec<Tileset>, TilemapError> {
+    let first_gid = tiledmap.tilesets.first().map_or(0, |t| t.firstgid);
+    let mut tilesets = Vec::with_capacity(tiledmap.tilesets.len());
+    for (i, t) in tiledmap.tilesets.iter().enumerate() {
+        if let Some(source) = &t.source {
+            return Err(TilemapError::UnsupportedExternalTileset(source.clone()));
+        }
+        let clip_columns = Some(clip.w as u32 / tiledmap.tile_width as u32).filter(|_| i == 0);
+        let columns = t
+            .columns
+            .or(clip_columns)
+            .filter(|columns| *columns > 0)
+            .ok_or(TilemapError::MissingField("columns"))?;
+        let clip_rows = Some(clip.h as u32 / tiledmap.tile_height as u32).filter(|_| i == 0);
+        let tile_count = t
+            .tilecount
+            .or_else(|| clip_rows.map(|rows| rows * columns))
+            .ok_or(TilemapError::MissingField("tilecount"))?;
+        tilesets.push(Tileset {
+            origin: if i == 0 { vec2(clip.x, clip.y) } else { vec2(0.0, 0.0) },
+            columns,
+            tile_count,
+            first_id: t.firstgid.saturating_sub(first_gid),
+        });
+    }
+    Ok(tilesets)
+}
+
+fn transform_tiledlayer(tiledmap: &TiledMap) -> Result<Vec<Layer>, TilemapError> {
+    let width = tiledmap.width as usize;
+    let height = tiledmap.height as usize;
+    let mut layers: Vec<Layer> = Vec::with_capacity(tiledmap.layers.len());
+    // tiled stores layers bottom to top, which already is our draw order
+    for tiledlayer in tiledmap.layers.iter() {
+        let mut layer = Layer {
+            tiles: VecGrid::new(width, height),
+            name: tiledlayer.name.clone(),
+            visibility: tiledlayer.visible.unwrap_or(true),
+            ..Layer::default()
+        };
+        layer.color.a = tiledlayer.opacity.unwrap_or(1.0);
+        let gids = tiledlayer.data.as_deref().ok_or(TilemapError::MissingField("data"))?;
+        for (i, gid) in gids.iter().enumerate().filter(|(_, gid)| **gid != 0) {
+            let x = (i % width) as i32;
+            let y = (i / width) as i32;
+            if i >= width * height {
+                return Err(TilemapError::TileOutOfRange {
+                    layer: tiledlayer.name.clone(),
+                    x,
+                    y,
+                });
+            }
+            if let Some((tileset, id, flip)) = tiledmap.resolve_gid(*gid) {
+                let shift_x = if flip.flip_x { tiledmap.tile_width } else { 0 };
+                let dest_width = if flip.flip_x { -tiledmap.tile_width } else { tiledmap.tile_width };
+                let tile = Tile {
+                    id,
+                    x,
+                    y,
+                    position_x: (x * tiledmap.tile_width + shift_x) as f32,
+                    position_y: (y * tiledmap.tile_height) as f32,
+                    rotation: flip.rotation(),
+                    dest_size: vec2(dest_width as f32, tiledmap.tile_height as f32),
+                    tileset,
+                    alpha: 1.0,
+                };
+                layer.tiles.set(tile, x as usize, y as usize);
+            } else {
+                return Err(TilemapError::UnknownTileId {
+                    layer: tiledlayer.name.clone(),
+                    id: *gid,
+                });
+            }
+        }
+        layers.push(layer);
+    }
+    Ok(layers)
 }
 /*
 fn draw_everything(rectangle: &Rect) -> bool{
@@ -387,6 +645,8 @@ impl Default for Tile {
             position_y: 0.0,
             rotation: 0.0,
             dest_size: vec2(0.0, 0.0),
+            tileset: 0,
+            alpha: 1.0,
         }
     }
 }
@@ -1,13 +1,15 @@
+use crate::tilemap::error::TilemapError;
 use macroquad::prelude::*;
 use nanoserde::DeJson;
 use std::collections::HashMap;
 
 #[allow(dead_code)]
 impl PyxelTilemap {
-    pub fn new(data: &str) -> PyxelTilemap {
-        let mut pyxeltilemap: PyxelTilemap = DeJson::deserialize_json(data).unwrap();
+    pub fn new(data: &str) -> Result<PyxelTilemap, TilemapError> {
+        let mut pyxeltilemap: PyxelTilemap =
+            DeJson::deserialize_json(data).map_err(|e| TilemapError::Json(format!("{:?}", e)))?;
         remodel(&mut pyxeltilemap);
-        pyxeltilemap
+        Ok(pyxeltilemap)
     }
     pub fn get_id_at_position(&self, layer: Layers, position: Vec2) -> Option<i32> {
         let x = position.x as i32 / self.tile_width;
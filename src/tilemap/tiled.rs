@@ -0,0 +1,408 @@
+use crate::tilemap::error::TilemapError;
+use nanoserde::DeJson;
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const GID_MASK: u32 = !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY);
+
+#[allow(dead_code)]
+impl TiledMap {
+    pub fn from_json(data: &str) -> Result<TiledMap, TilemapError> {
+        let mut tiledmap: TiledMap =
+            DeJson::deserialize_json(data).map_err(|e| TilemapError::Json(format!("{:?}", e)))?;
+        tiledmap.layers = flatten_layers(std::mem::take(&mut tiledmap.layers));
+        Ok(tiledmap)
+    }
+
+    pub fn from_tmx(data: &str) -> Result<TiledMap, TilemapError> {
+        parse_tmx(data).map_err(TilemapError::Tmx)
+    }
+
+    /// Tile ids count from the first tile of the first tileset, so logic tiles keep their id
+    /// no matter which tileset they are drawn from.
+    pub fn resolve_gid(&self, raw_gid: u32) -> Option<(usize, u32, TileFlip)> {
+        let gid = raw_gid & GID_MASK;
+        if gid == 0 {
+            return None;
+        }
+        let index = self.tilesets.iter().rposition(|t| t.firstgid <= gid)?;
+        let flip = TileFlip::from_gid(raw_gid);
+        Some((index, gid.checked_sub(self.tilesets[0].firstgid)?, flip))
+    }
+}
+
+#[derive(Clone, Debug, Default, DeJson)]
+pub struct TiledMap {
+    pub width: i32,
+    pub height: i32,
+    #[nserde(rename = "tilewidth")]
+    pub tile_width: i32,
+    #[nserde(rename = "tileheight")]
+    pub tile_height: i32,
+    pub layers: Vec<TiledLayer>,
+    pub tilesets: Vec<TiledTileset>,
+}
+
+#[derive(Clone, Debug, Default, DeJson)]
+pub struct TiledLayer {
+    pub name: String,
+    #[nserde(rename = "type")]
+    pub layer_type: String,
+    pub data: Option<Vec<u32>>,
+    pub visible: Option<bool>,
+    pub opacity: Option<f32>,
+    pub layers: Option<Vec<TiledLayer>>,
+}
+
+#[derive(Clone, Debug, Default, DeJson)]
+pub struct TiledTileset {
+    pub firstgid: u32,
+    pub columns: Option<u32>,
+    pub tilecount: Option<u32>,
+    pub source: Option<String>,
+}
+
+/// Tiled flip flags expressed the way the pyxeledit importer stores them:
+/// a mirror on the x axis followed by a clockwise rotation in quarter turns.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TileFlip {
+    pub flip_x: bool,
+    pub quarter_turns: u8,
+}
+
+impl TileFlip {
+    fn from_gid(gid: u32) -> TileFlip {
+        let h = gid & FLIPPED_HORIZONTALLY != 0;
+        let v = gid & FLIPPED_VERTICALLY != 0;
+        let d = gid & FLIPPED_DIAGONALLY != 0;
+        let (quarter_turns, flip_x) = match (d, h, v) {
+            (false, false, false) => (0, false),
+            (false, true, false) => (0, true),
+            (false, false, true) => (2, true),
+            (false, true, true) => (2, false),
+            (true, false, false) => (3, true),
+            (true, true, false) => (1, false),
+            (true, false, true) => (3, false),
+            (true, true, true) => (1, true),
+        };
+        TileFlip { flip_x, quarter_turns }
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.quarter_turns as f32 * std::f32::consts::FRAC_PI_2
+    }
+}
+
+fn flatten_layers(layers: Vec<TiledLayer>) -> Vec<TiledLayer> {
+    let mut flat = Vec::with_capacity(layers.len());
+    for mut layer in layers {
+        match layer.layer_type.as_str() {
+            "tilelayer" => flat.push(layer),
+            "group" => {
+                if let Some(children) = layer.layers.take() {
+                    flat.extend(flatten_layers(children));
+                }
+            }
+            _ => {}
+        }
+    }
+    flat
+}
+
+fn parse_tmx(data: &str) -> Result<TiledMap, String> {
+    let mut tiledmap = TiledMap::default();
+    let mut layer: Option<TiledLayer> = None;
+    let mut rest = data;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if rest.starts_with("<?") || rest.starts_with("<!") {
+            let end = rest.find('>').ok_or("unterminated declaration")?;
+            rest = &rest[end + 1..];
+            continue;
+        }
+        let end = rest.find('>').ok_or("unterminated tag")?;
+        let tag = XmlTag::parse(&rest[1..end]);
+        rest = &rest[end + 1..];
+
+        match (tag.name, tag.closing) {
+            ("map", false) => {
+                tiledmap.width = tag.number("width")?;
+                tiledmap.height = tag.number("height")?;
+                tiledmap.tile_width = tag.number("tilewidth")?;
+                tiledmap.tile_height = tag.number("tileheight")?;
+            }
+            ("tileset", false) => tiledmap.tilesets.push(TiledTileset {
+                firstgid: tag.number("firstgid")?,
+                columns: tag.attribute("columns").and_then(|c| c.parse().ok()),
+                tilecount: tag.attribute("tilecount").and_then(|c| c.parse().ok()),
+                source: tag.attribute("source"),
+            }),
+            ("layer", false) => {
+                let l = TiledLayer {
+                    name: tag.attribute("name").unwrap_or_default(),
+                    layer_type: "tilelayer".to_string(),
+                    data: Some(Vec::new()),
+                    visible: Some(tag.attribute("visible").as_deref() != Some("0")),
+                    opacity: tag.attribute("opacity").and_then(|o| o.parse().ok()),
+                    layers: None,
+                };
+                if tag.self_closing {
+                    tiledmap.layers.push(l);
+                } else {
+                    layer = Some(l);
+                }
+            }
+            ("layer", true) => {
+                if let Some(l) = layer.take() {
+                    tiledmap.layers.push(l);
+                }
+            }
+            ("data", false) => {
+                if let Some(l) = layer.as_mut() {
+                    match tag.attribute("encoding").as_deref() {
+                        Some("csv") => {
+                            let content = &rest[..rest.find('<').unwrap_or(rest.len())];
+                            let gids = l.data.get_or_insert_with(Vec::new);
+                            for value in content.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+                                gids.push(value.parse().map_err(|_| format!("invalid gid '{}'", value))?);
+                            }
+                        }
+                        None => {}
+                        Some(encoding) => return Err(format!("unsupported layer encoding '{}'", encoding)),
+                    }
+                }
+            }
+            ("tile", false) => {
+                // uncompressed xml layer data, one <tile gid=".."/> per cell
+                if let Some(l) = layer.as_mut() {
+                    let gid = tag.attribute("gid").and_then(|g| g.parse().ok()).unwrap_or(0);
+                    l.data.get_or_insert_with(Vec::new).push(gid);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(tiledmap)
+}
+
+struct XmlTag<'a> {
+    name: &'a str,
+    closing: bool,
+    self_closing: bool,
+    attributes: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> XmlTag<'a> {
+    fn parse(content: &'a str) -> XmlTag<'a> {
+        let closing = content.starts_with('/');
+        let self_closing = content.ends_with('/');
+        let content = content.trim_start_matches('/').trim_end_matches('/');
+        let name_end = content.find(char::is_whitespace).unwrap_or(content.len());
+        let mut attributes = Vec::new();
+        let mut rest = &content[name_end..];
+        while let Some(eq) = rest.find('=') {
+            let key = rest[..eq].trim();
+            let after = rest[eq + 1..].trim_start();
+            let quote = match after.chars().next() {
+                Some(q) if q == '"' || q == '\'' => q,
+                _ => break,
+            };
+            let value_end = match after[1..].find(quote) {
+                Some(i) => i + 1,
+                None => break,
+            };
+            attributes.push((key, &after[1..value_end]));
+            rest = &after[value_end + 1..];
+        }
+        XmlTag {
+            name: &content[..name_end],
+            closing,
+            self_closing,
+            attributes,
+        }
+    }
+
+    fn attribute(&self, key: &str) -> Option<String> {
+        self.attributes.iter().find(|(k, _)| *k == key).map(|(_, v)| unescape(v))
+    }
+
+    fn number<T: std::str::FromStr>(&self, key: &str) -> Result<T, String> {
+        self.attribute(key)
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| format!("<{}> is missing a numeric '{}' attribute", self.name, key))
+    }
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::Tilemap;
+    use macroquad::prelude::Rect;
+
+    const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.9" orientation="orthogonal" width="3" height="2" tilewidth="8" tileheight="8">
+ <tileset firstgid="1" name="game" tilewidth="8" tileheight="8" tilecount="16" columns="4">
+  <image source="game.png" width="32" height="32"/>
+ </tileset>
+ <tileset firstgid="17" name="props" tilewidth="8" tileheight="8" tilecount="4" columns="2">
+  <image source="props.png" width="16" height="16"/>
+ </tileset>
+ <layer id="1" name="ground" width="3" height="2" opacity="0.5">
+  <data encoding="csv">
+1,0,2,
+0,17,3
+</data>
+ </layer>
+ <layer id="2" name="logic" width="3" height="2" visible="0">
+  <data>
+   <tile gid="5"/><tile/><tile/>
+   <tile/><tile/><tile gid="6"/>
+  </data>
+ </layer>
+</map>"#;
+
+    const JSON: &str = r#"{
+        "width": 2, "height": 2, "tilewidth": 8, "tileheight": 8, "orientation": "orthogonal",
+        "tilesets": [
+            {"firstgid": 1, "name": "game", "image": "game.png", "columns": 4, "tilecount": 16},
+            {"firstgid": 17, "name": "props", "image": "props.png", "columns": 2, "tilecount": 4}
+        ],
+        "layers": [
+            {"name": "ground", "type": "tilelayer", "data": [1, 0, 0, 18]},
+            {"name": "things", "type": "group", "layers": [
+                {"name": "logic", "type": "tilelayer", "visible": false, "data": [0, 5, 0, 0]},
+                {"name": "spawns", "type": "objectgroup"},
+                {"name": "deeper", "type": "group", "layers": [
+                    {"name": "decor", "type": "tilelayer", "opacity": 0.25, "data": [0, 0, 2, 0]}
+                ]}
+            ]}
+        ]
+    }"#;
+
+    fn layer_names(tiledmap: &TiledMap) -> Vec<&str> {
+        tiledmap.layers.iter().map(|l| l.name.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_a_minimal_tmx_map() {
+        let tiledmap = TiledMap::from_tmx(TMX).unwrap();
+        assert_eq!((tiledmap.width, tiledmap.height), (3, 2));
+        assert_eq!((tiledmap.tile_width, tiledmap.tile_height), (8, 8));
+        assert_eq!(tiledmap.tilesets.len(), 2);
+        assert_eq!(tiledmap.tilesets[0].columns, Some(4));
+        assert_eq!(tiledmap.tilesets[1].firstgid, 17);
+        assert_eq!(layer_names(&tiledmap), ["ground", "logic"]);
+        assert_eq!(tiledmap.layers[0].data.as_deref(), Some(&[1, 0, 2, 0, 17, 3][..]));
+        assert_eq!(tiledmap.layers[0].opacity, Some(0.5));
+        assert_eq!(tiledmap.layers[1].data.as_deref(), Some(&[5, 0, 0, 0, 0, 6][..]));
+        assert_eq!(tiledmap.layers[1].visible, Some(false));
+    }
+
+    #[test]
+    fn reads_a_minimal_json_map() {
+        let tiledmap = TiledMap::from_json(JSON).unwrap();
+        assert_eq!((tiledmap.width, tiledmap.height), (2, 2));
+        assert_eq!(tiledmap.tilesets[1].tilecount, Some(4));
+        assert_eq!(tiledmap.layers[0].data.as_deref(), Some(&[1, 0, 0, 18][..]));
+    }
+
+    #[test]
+    fn flattens_group_layers_in_draw_order() {
+        let tiledmap = TiledMap::from_json(JSON).unwrap();
+        assert_eq!(layer_names(&tiledmap), ["ground", "logic", "decor"]);
+        assert_eq!(tiledmap.layers[1].visible, Some(false));
+        assert_eq!(tiledmap.layers[2].opacity, Some(0.25));
+    }
+
+    #[test]
+    fn reads_flip_bits() {
+        let flip = |flags| TileFlip::from_gid(flags | 1);
+        assert_eq!(flip(0), TileFlip { flip_x: false, quarter_turns: 0 });
+        assert_eq!(flip(FLIPPED_HORIZONTALLY), TileFlip { flip_x: true, quarter_turns: 0 });
+        assert_eq!(flip(FLIPPED_VERTICALLY), TileFlip { flip_x: true, quarter_turns: 2 });
+        assert_eq!(flip(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY), TileFlip { flip_x: false, quarter_turns: 2 });
+        assert_eq!(flip(FLIPPED_DIAGONALLY | FLIPPED_HORIZONTALLY), TileFlip { flip_x: false, quarter_turns: 1 });
+        assert_eq!(flip(FLIPPED_DIAGONALLY | FLIPPED_VERTICALLY), TileFlip { flip_x: false, quarter_turns: 3 });
+        assert_eq!(flip(FLIPPED_DIAGONALLY).rotation(), 3.0 * std::f32::consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn resolves_gids_across_tilesets() {
+        let tiledmap = TiledMap::from_json(JSON).unwrap();
+        assert!(tiledmap.resolve_gid(0).is_none());
+        assert_eq!(tiledmap.resolve_gid(1).map(|(t, id, _)| (t, id)), Some((0, 0)));
+        assert_eq!(tiledmap.resolve_gid(16).map(|(t, id, _)| (t, id)), Some((0, 15)));
+        // ids count on across tilesets, so the first tile of the second tileset is 16
+        assert_eq!(tiledmap.resolve_gid(17).map(|(t, id, _)| (t, id)), Some((1, 16)));
+        let (tileset, id, flip) = tiledmap.resolve_gid(18 | FLIPPED_HORIZONTALLY).unwrap();
+        assert_eq!((tileset, id), (1, 17));
+        assert!(flip.flip_x);
+    }
+
+    #[test]
+    fn builds_a_tilemap_from_several_tilesets() {
+        let clip = Rect::new(0.0, 0.0, 32.0, 32.0);
+        let tilemap = Tilemap::from_tiled(clip, JSON).unwrap();
+        assert_eq!(tilemap.tileset_count(), 2);
+        let ground = tilemap.get_layer_id("ground").unwrap();
+        assert_eq!(tilemap.get_id_at(ground, 0, 0), Some(0));
+        assert_eq!(tilemap.get_id_at(ground, 1, 1), Some(17));
+        let tilemap = Tilemap::from_tmx(clip, TMX).unwrap();
+        let logic = tilemap.get_layer_id("logic").unwrap();
+        assert_eq!(tilemap.get_id_at(logic, 2, 1), Some(5));
+    }
+
+    #[test]
+    fn rejects_malformed_maps() {
+        assert!(TiledMap::from_json("{\"width\": 2").is_err());
+        assert!(TiledMap::from_tmx("<map width=\"2\" height=\"2\" tilewidth=\"8\">").is_err());
+        assert!(TiledMap::from_tmx("<map width=\"2\" height=\"2\" tilewidth=\"8\" tileheight=\"8\"").is_err());
+        let base64 = TMX.replace("encoding=\"csv\"", "encoding=\"base64\"");
+        assert!(TiledMap::from_tmx(&base64).is_err());
+        let bad_gid = TMX.replace("1,0,2", "1,x,2");
+        assert!(TiledMap::from_tmx(&bad_gid).is_err());
+    }
+
+    #[test]
+    fn rejects_maps_the_game_can_not_hold() {
+        let clip = Rect::new(0.0, 0.0, 32.0, 32.0);
+        let tmx = |width: &str, tile: &str| {
+            TMX.replacen("width=\"3\"", &format!("width=\"{}\"", width), 1)
+                .replacen("tilewidth=\"8\"", &format!("tilewidth=\"{}\"", tile), 1)
+        };
+        assert!(Tilemap::from_tmx(clip, &tmx("3", "8")).is_ok());
+        assert!(matches!(Tilemap::from_tmx(clip, &tmx("0", "8")), Err(TilemapError::InvalidMapSize { .. })));
+        assert!(matches!(Tilemap::from_tmx(clip, &tmx("-3", "8")), Err(TilemapError::InvalidMapSize { .. })));
+        assert!(matches!(Tilemap::from_tmx(clip, &tmx("3", "16")), Err(TilemapError::UnsupportedTileSize { .. })));
+        assert!(matches!(Tilemap::from_tmx(clip, &tmx("3", "0")), Err(TilemapError::UnsupportedTileSize { .. })));
+        let unknown = JSON.replace("[1, 0, 0, 18]", "[1, 0, 0, 40]");
+        assert!(matches!(Tilemap::from_tiled(clip, &unknown), Err(TilemapError::UnknownTileId { .. })));
+        let external = JSON.replace(r#""name": "props", "image": "props.png", "columns": 2, "tilecount": 4"#, r#""source": "props.tsj""#);
+        assert!(matches!(Tilemap::from_tiled(clip, &external), Err(TilemapError::UnsupportedExternalTileset(_))));
+        let tsx = TMX.replace(r#"name="props" tilewidth="8" tileheight="8" tilecount="4" columns="2">"#, r#"source="props.tsx">"#);
+        assert!(matches!(Tilemap::from_tmx(clip, &tsx), Err(TilemapError::UnsupportedExternalTileset(_))));
+    }
+
+    #[test]
+    fn draws_tiles_row_by_row_from_wide_tilesets() {
+        let map = JSON.replace(r#""columns": 4, "tilecount": 16"#, r#""columns": 16, "tilecount": 128"#).replace("[1, 0, 0, 18]", "[10, 0, 0, 18]");
+        let tilemap = Tilemap::from_tiled(Rect::new(0.0, 0.0, 128.0, 64.0), &map).unwrap();
+        let ground = tilemap.get_layer_id("ground").unwrap();
+        let tile = tilemap.layers[ground.0].tiles.get(0, 0).unwrap();
+        assert_eq!(tile.id, 9);
+        assert_eq!(tilemap.get_tile_source(tile), Rect::new(72.0, 0.0, 8.0, 8.0));
+        assert_eq!(tilemap.get_rect_from_id(9), Rect::new(72.0, 0.0, 8.0, 8.0));
+        let prop = tilemap.layers[ground.0].tiles.get(1, 1).unwrap();
+        assert_eq!(tilemap.get_tile_source(prop), Rect::new(8.0, 0.0, 8.0, 8.0));
+    }
+}
@@ -0,0 +1,30 @@
+use macroquad::prelude::*;
+
+pub const TICK_RATE: u32 = 60;
+pub const TICK: f32 = 1.0 / TICK_RATE as f32;
+/// Frame time above this is dropped, so a stall (window drag, loading) does not fast forward the game.
+const MAX_FRAME_TIME: f32 = 0.25;
+
+/// Accumulates rendered frame time and hands it out in fixed ticks,
+/// so the simulation runs at the same rate whatever the refresh rate of the monitor.
+#[derive(Debug, Clone, Default)]
+pub struct FixedStep {
+    accumulator: f32,
+}
+
+impl FixedStep {
+    pub fn new() -> FixedStep {
+        FixedStep { accumulator: 0.0 }
+    }
+
+    pub fn ticks(&mut self) -> u32 {
+        self.accumulator += get_frame_time().min(MAX_FRAME_TIME);
+        let ticks = (self.accumulator / TICK) as u32;
+        self.accumulator -= ticks as f32 * TICK;
+        ticks
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
@@ -1,5 +1,7 @@
 use macroquad::prelude::*;
 
+pub mod fixed_step;
+pub mod run_timer;
 pub(crate) mod timer;
 pub(crate) mod tween;
 pub(crate) mod vecgrid;
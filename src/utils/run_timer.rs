@@ -0,0 +1,79 @@
+use crate::utils::fixed_step::TICK_RATE;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Split {
+    pub level: String,
+    pub ticks: u32,
+}
+
+/// Clock of a run counted in simulation ticks, so it runs exactly as fast as the game does.
+#[derive(Debug, Clone, Default)]
+pub struct RunTimer {
+    ticks: u32,
+    level_start: u32,
+    deaths: u32,
+    splits: Vec<Split>,
+    running: bool,
+}
+
+impl RunTimer {
+    pub fn new() -> RunTimer {
+        RunTimer::default()
+    }
+
+    /// Starts the clock, a continued run passes the time and deaths it already has.
+    pub fn start(&mut self, run_ticks: u32, level_ticks: u32, deaths: u32) {
+        self.ticks = run_ticks;
+        self.level_start = run_ticks.saturating_sub(level_ticks);
+        self.deaths = deaths;
+        self.splits.clear();
+        self.running = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    pub fn tick(&mut self) {
+        if self.running {
+            self.ticks += 1;
+        }
+    }
+
+    pub fn die(&mut self) {
+        self.deaths += 1;
+    }
+
+    /// Ends the split of `level` and starts the next one, returns the ticks spent on `level`.
+    pub fn split(&mut self, level: &str) -> u32 {
+        let ticks = self.level_ticks();
+        self.splits.push(Split {
+            level: level.to_string(),
+            ticks,
+        });
+        self.level_start = self.ticks;
+        ticks
+    }
+
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    pub fn level_ticks(&self) -> u32 {
+        self.ticks - self.level_start
+    }
+
+    pub fn deaths(&self) -> u32 {
+        self.deaths
+    }
+
+    pub fn splits(&self) -> &[Split] {
+        &self.splits
+    }
+}
+
+/// Formats ticks as `m:ss.cc`.
+pub fn format_ticks(ticks: u32) -> String {
+    let centis = ticks as u64 * 100 / TICK_RATE as u64;
+    format!("{}:{:02}.{:02}", centis / 6000, centis / 100 % 60, centis % 100)
+}
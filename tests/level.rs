@@ -0,0 +1,382 @@
+use macroquad::prelude::*;
+use rustyjam21::entity::body::{PlayerBody, PlayerEvent, PlayerInput};
+use rustyjam21::entity::physics::{PhysicsOverride, PlayerPhysics};
+use rustyjam21::scene::game::GameState;
+use rustyjam21::scene::illusion;
+use rustyjam21::scene::level::{load_tilemap, Level, LevelInfo, LevelSource, MapLayers};
+use rustyjam21::scene::tile_properties::TileProperties;
+use rustyjam21::tilemap::Tilemap;
+use rustyjam21::utils::fixed_step::TICK_RATE;
+
+fn load_game_map() -> (Tilemap, MapLayers, TileProperties) {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/maps");
+    let source = LevelSource {
+        info: LevelInfo {
+            name: "illusions".to_string(),
+            map: "game.json".to_string(),
+            tileset: "game.png".to_string(),
+            extra_tilesets: vec![],
+            properties: "tiles.json".to_string(),
+            physics: String::new(),
+        },
+        map: std::fs::read(format!("{}/game.json", dir)).unwrap(),
+        tileset: std::fs::read(format!("{}/game.png", dir)).unwrap(),
+        extra_tilesets: vec![],
+        properties: std::fs::read(format!("{}/tiles.json", dir)).unwrap(),
+        physics: vec![],
+    };
+    let (width, height) = png_size(&source.tileset);
+    load_tilemap(&source, Rect::new(0.0, 0.0, width, height)).unwrap()
+}
+
+/// Area of the tileset of `tiled_source` maps, 16 by 8 tiles.
+const TILED_CLIP: Rect = Rect {
+    x: 0.0,
+    y: 0.0,
+    w: 128.0,
+    h: 64.0,
+};
+
+/// A level of the Tiled json `map` with the tiles of tiles.json, tile gids are the ids of tiles.json plus one.
+fn tiled_source(map: &str) -> LevelSource {
+    LevelSource {
+        info: LevelInfo {
+            name: "test".to_string(),
+            map: "test.tmj".to_string(),
+            tileset: "game.png".to_string(),
+            extra_tilesets: vec![],
+            properties: "tiles.json".to_string(),
+            physics: String::new(),
+        },
+        map: map.as_bytes().to_vec(),
+        tileset: vec![],
+        extra_tilesets: vec![],
+        properties: std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/maps/tiles.json")).unwrap(),
+        physics: vec![],
+    }
+}
+
+/// Size from the IHDR chunk, which always directly follows the png signature.
+fn png_size(png: &[u8]) -> (f32, f32) {
+    let width = u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+    let height = u32::from_be_bytes([png[20], png[21], png[22], png[23]]);
+    (width as f32, height as f32)
+}
+
+const LEFT: PlayerInput = PlayerInput {
+    left: true,
+    right: false,
+    jump: false,
+    duck: false,
+    respawn: false,
+};
+
+fn tiles_with(collision: &str) -> TileProperties {
+    let json = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/maps/tiles.json")).unwrap();
+    let patched = json.replace(r#""collision": []"#, &format!(r#""collision": {}"#, collision));
+    assert_ne!(patched, json, "tiles.json has no empty collision table to patch");
+    TileProperties::from_json(&patched).unwrap()
+}
+
+fn spawn(tilemap: &mut Tilemap, layers: &MapLayers, properties: &TileProperties) -> PlayerBody {
+    let mut body = PlayerBody::new();
+    body.reset(tilemap, layers, properties);
+    run_script(&mut body, tilemap, layers, properties, &[(TICK_RATE, PlayerInput::default())], TICK_RATE);
+    body
+}
+
+/// Runs the scripted `(ticks, input)` steps, then keeps the last input
+/// until the player wins, dies or `max_ticks` are over.
+fn run_script(body: &mut PlayerBody, tilemap: &mut Tilemap, layers: &MapLayers, properties: &TileProperties, script: &[(u32, PlayerInput)], max_ticks: u32) -> (Option<GameState>, Vec<PlayerEvent>) {
+    let mut inputs = vec![];
+    for (ticks, input) in script {
+        inputs.extend(vec![*input; *ticks as usize]);
+    }
+    let last = script.last().map(|(_, input)| *input).unwrap_or_default();
+    let mut events = vec![];
+    for tick in 0..max_ticks as usize {
+        let input = inputs.get(tick).copied().unwrap_or(last);
+        let state = body.update(tilemap, layers, properties, &input);
+        events.extend(body.take_events());
+        if state.is_some() {
+            return (state, events);
+        }
+    }
+    (None, events)
+}
+
+#[test]
+fn spawn_and_exit_are_on_the_logic_layer() {
+    let (tilemap, layers, properties) = load_game_map();
+    assert_eq!(properties.find(&tilemap, layers.logic, |p| p.spawn), vec![vec2(192.0, 208.0)]);
+    assert!(!properties.find(&tilemap, layers.logic, |p| p.exit).is_empty());
+}
+
+#[test]
+fn standing_still_neither_wins_nor_dies() {
+    let (mut tilemap, layers, properties) = load_game_map();
+    let mut body = spawn(&mut tilemap, &layers, &properties);
+    let (state, events) = run_script(&mut body, &mut tilemap, &layers, &properties, &[(1, PlayerInput::default())], TICK_RATE * 10);
+    assert_eq!(state, None);
+    assert!(!events.contains(&PlayerEvent::Died));
+}
+
+#[test]
+fn scripted_run_reaches_the_exit() {
+    let (mut tilemap, layers, properties) = load_game_map();
+    let script = [
+        // hop onto the step left of the spawn
+        (30, PlayerInput { jump: true, ..LEFT }),
+        (10, LEFT),
+        // jump up to the gap below the ceiling and crawl through it onto the block, the exit is behind it
+        (11, PlayerInput { jump: true, ..LEFT }),
+        (1, PlayerInput { duck: true, ..LEFT }),
+    ];
+    let mut body = spawn(&mut tilemap, &layers, &properties);
+    let (state, events) = run_script(&mut body, &mut tilemap, &layers, &properties, &script, TICK_RATE * 10);
+    assert!(!events.contains(&PlayerEvent::Died), "touched a kill tile: {:?}", events);
+    assert_eq!(state, Some(GameState::WIN));
+    assert_eq!(events.iter().filter(|e| **e == PlayerEvent::Jumped).count(), 2);
+    assert!(events.contains(&PlayerEvent::Landed), "never landed: {:?}", events);
+    assert_eq!(events.last(), Some(&PlayerEvent::Won));
+}
+
+#[test]
+fn walking_into_a_wall_stops_flush_against_it() {
+    let (mut tilemap, layers, properties) = load_game_map();
+    let mut body = spawn(&mut tilemap, &layers, &properties);
+    run_script(&mut body, &mut tilemap, &layers, &properties, &[(TICK_RATE, LEFT)], TICK_RATE);
+    // the step left of the spawn ends at x 192, the hitbox starts one pixel into the sprite
+    assert_eq!(body.position, vec2(191.0, 208.0));
+}
+
+#[test]
+fn collision_tiles_listed_for_other_properties_stay_solid() {
+    let (mut tilemap, layers, _) = load_game_map();
+    let properties = tiles_with(r#"[{ "id": 97, "friction": 0.5 }]"#);
+    // the floor below the spawn becomes slippery
+    for position in [vec2(192.0, 224.0), vec2(200.0, 224.0)].iter() {
+        tilemap.set_tileid_at(layers.collision, Some(97), *position);
+    }
+    let mut body = spawn(&mut tilemap, &layers, &properties);
+    run_script(&mut body, &mut tilemap, &layers, &properties, &[(TICK_RATE, PlayerInput::default())], TICK_RATE);
+    assert_eq!(body.position().y, 208.0, "fell through the slippery floor");
+}
+
+#[test]
+fn slopes_are_walked_up_and_down_without_falling() {
+    let (mut tilemap, layers, _) = load_game_map();
+    let properties = tiles_with(r#"[{ "id": 98, "solid": true, "shape": "slope_down" }]"#);
+    // the step left of the spawn becomes a slope up to the block behind it
+    tilemap.set_tileid_at(layers.collision, Some(98), vec2(184.0, 216.0));
+    let mut body = spawn(&mut tilemap, &layers, &properties);
+    let (_, events) = run_script(&mut body, &mut tilemap, &layers, &properties, &[(TICK_RATE * 2, LEFT)], TICK_RATE * 2);
+    // the feet sink into the slope up to the middle of the hitbox
+    assert_eq!(body.position, vec2(183.0, 203.5), "did not walk up to the block");
+    assert!(!events.contains(&PlayerEvent::Landed), "fell while walking up: {:?}", events);
+    let right = PlayerInput { right: true, ..Default::default() };
+    let (_, events) = run_script(&mut body, &mut tilemap, &layers, &properties, &[(14, right)], 14);
+    assert!(!events.contains(&PlayerEvent::Landed), "fell while walking down: {:?}", events);
+    run_script(&mut body, &mut tilemap, &layers, &properties, &[(TICK_RATE, PlayerInput::default())], TICK_RATE);
+    assert_eq!(body.position.y, 208.0, "did not get back down to the floor");
+}
+
+#[test]
+fn shaped_tiles_block_from_below_and_the_sides() {
+    let (mut tilemap, layers, _) = load_game_map();
+    let properties = tiles_with(r#"[{ "id": 96, "shape": "half" }, { "id": 98, "shape": "slope_up" }]"#);
+    // a half tile above the spawn and a slope rising towards it in place of the step left of it
+    tilemap.set_tileid_at(layers.collision, Some(96), vec2(192.0, 192.0));
+    tilemap.set_tileid_at(layers.collision, Some(98), vec2(184.0, 216.0));
+    let mut body = spawn(&mut tilemap, &layers, &properties);
+    let jump = PlayerInput { jump: true, ..Default::default() };
+    let idle = PlayerInput::default();
+    let mut highest = body.position.y;
+    for tick in 0..TICK_RATE {
+        let input = if tick < 12 { jump } else { idle };
+        run_script(&mut body, &mut tilemap, &layers, &properties, &[(1, input)], 1);
+        highest = highest.min(body.position.y);
+    }
+    // the top of the hitbox stops at the bottom of the half tile
+    assert_eq!(highest, 197.0, "jumped into the half tile");
+    run_script(&mut body, &mut tilemap, &layers, &properties, &[(TICK_RATE, LEFT)], TICK_RATE);
+    assert_eq!(body.position, vec2(191.0, 208.0), "walked into the high side of the slope");
+}
+
+#[test]
+fn jumping_right_after_walking_off_a_ledge_still_jumps() {
+    let (mut tilemap, layers, properties) = load_game_map();
+    let mut body = spawn(&mut tilemap, &layers, &properties);
+    let right = PlayerInput { right: true, ..Default::default() };
+    let mut events = vec![];
+    // run towards the pit right of the spawn until the ground is gone
+    while body.position.y <= 208.0 {
+        events.extend(run_script(&mut body, &mut tilemap, &layers, &properties, &[(1, right)], 1).1);
+    }
+    events.extend(run_script(&mut body, &mut tilemap, &layers, &properties, &[(1, PlayerInput { jump: true, ..right })], 1).1);
+    assert_eq!(events, vec![PlayerEvent::Jumped]);
+}
+
+#[test]
+fn jump_pressed_just_before_landing_is_not_lost() {
+    let (mut tilemap, layers, properties) = load_game_map();
+    let mut body = spawn(&mut tilemap, &layers, &properties);
+    let jump = PlayerInput { jump: true, ..Default::default() };
+    let idle = PlayerInput::default();
+    let (_, mut events) = run_script(&mut body, &mut tilemap, &layers, &properties, &[(12, jump), (1, idle)], 13);
+    // wait for the way down, then tap jump a couple of ticks before touching the floor
+    let mut last_y = f32::MIN;
+    while body.position.y <= last_y || body.position.y < 204.0 {
+        last_y = body.position.y;
+        events.extend(run_script(&mut body, &mut tilemap, &layers, &properties, &[(1, idle)], 1).1);
+    }
+    events.extend(run_script(&mut body, &mut tilemap, &layers, &properties, &[(1, jump), (TICK_RATE, idle)], TICK_RATE + 1).1);
+    assert_eq!(events.iter().filter(|e| **e == PlayerEvent::Jumped).count(), 2, "{:?}", events);
+}
+
+#[test]
+fn illusions_are_revealed_by_touching_them() {
+    // ids as in tiles.json
+    const FAKE_WALL: u32 = 6;
+    const HIDDEN_PLATFORM: u32 = 7;
+    const REVEALED_WALL: u32 = 8;
+    const REVEALED_PLATFORM: u32 = 9;
+    let (mut tilemap, layers, properties) = load_game_map();
+    let mut body = spawn(&mut tilemap, &layers, &properties);
+    // the floor below the spawn becomes a hidden platform, the free tiles above the step left of it a fake wall
+    for position in [vec2(192.0, 224.0), vec2(200.0, 224.0)].iter() {
+        tilemap.set_tileid_at(layers.logic, Some(HIDDEN_PLATFORM), *position);
+    }
+    for position in [vec2(184.0, 200.0), vec2(184.0, 208.0)].iter() {
+        tilemap.set_tileid_at(layers.logic, Some(FAKE_WALL), *position);
+    }
+    // stand on the platform for a tick, then jump through the wall
+    let script = [(1, PlayerInput::default()), (30, PlayerInput { jump: true, ..LEFT }), (10, LEFT)];
+    let (_, events) = run_script(&mut body, &mut tilemap, &layers, &properties, &script, 41);
+    assert_eq!(events.iter().filter(|e| **e == PlayerEvent::RevealedIllusion).count(), 2);
+    assert_eq!(tilemap.get_id_at_position(layers.logic, vec2(200.0, 224.0)), Some(REVEALED_PLATFORM));
+    assert_eq!(tilemap.get_id_at_position(layers.logic, vec2(184.0, 200.0)), Some(REVEALED_WALL));
+}
+
+/// A spawn above a row of hidden platform tiles.
+const HIDDEN_PLATFORM_MAP: &str = r#"{
+    "width": 3, "height": 3, "tilewidth": 8, "tileheight": 8,
+    "tilesets": [{ "firstgid": 1, "columns": 16, "tilecount": 128 }],
+    "layers": [
+        { "name": "draw", "type": "tilelayer", "data": [0, 0, 0, 1, 1, 1, 0, 0, 0] },
+        { "name": "logic", "type": "tilelayer", "data": [0, 3, 0, 8, 8, 8, 0, 0, 0] },
+        { "name": "collision", "type": "tilelayer", "data": [0, 0, 0, 0, 0, 0, 0, 0, 0] }
+    ]
+}"#;
+
+#[test]
+fn restarting_a_level_hides_revealed_illusions_again() {
+    const HIDDEN_PLATFORM: u32 = 7;
+    const REVEALED_PLATFORM: u32 = 9;
+    let source = tiled_source(HIDDEN_PLATFORM_MAP);
+    let mut level = Level::with_textures(&source, vec![], TILED_CLIP).unwrap();
+    let platform = vec2(8.0, 8.0);
+    assert!(illusion::reveal(&mut level.tilemap, level.layers.logic, &level.properties, platform));
+    assert_eq!(level.tilemap.get_id_at_position(level.layers.logic, vec2(16.0, 8.0)), Some(REVEALED_PLATFORM));
+    level.restart(&source).unwrap();
+    assert_eq!(level.tilemap.get_id_at_position(level.layers.logic, platform), Some(HIDDEN_PLATFORM));
+    assert_eq!(level.tilemap.get_id_at_position(level.layers.logic, vec2(16.0, 8.0)), Some(HIDDEN_PLATFORM));
+}
+
+#[test]
+fn one_way_platforms_can_be_dropped_through_and_jumped_onto() {
+    let (mut tilemap, layers, _) = load_game_map();
+    let properties = tiles_with(r#"[{ "id": 99, "one_way": true }]"#);
+    // the floor below the spawn becomes a one-way platform with solid ground one tile further down
+    for position in [vec2(192.0, 224.0), vec2(200.0, 224.0)].iter() {
+        tilemap.set_tileid_at(layers.collision, Some(99), *position);
+    }
+    let mut body = spawn(&mut tilemap, &layers, &properties);
+    let idle = PlayerInput::default();
+    run_script(&mut body, &mut tilemap, &layers, &properties, &[(TICK_RATE, idle)], TICK_RATE);
+    assert_eq!(body.position().y, 208.0, "fell through the platform while standing on it");
+    let drop = [(2, PlayerInput { duck: true, jump: true, ..idle }), (TICK_RATE, idle)];
+    run_script(&mut body, &mut tilemap, &layers, &properties, &drop, TICK_RATE + 2);
+    assert_eq!(body.position().y, 216.0, "did not drop through the platform");
+    let jump = [(12, PlayerInput { jump: true, ..idle }), (TICK_RATE, idle)];
+    run_script(&mut body, &mut tilemap, &layers, &properties, &jump, TICK_RATE + 12);
+    assert_eq!(body.position().y, 208.0, "did not jump back onto the platform");
+}
+
+#[test]
+fn physics_file_holds_the_defaults_and_levels_change_only_what_they_list() {
+    let json = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/physics.json")).unwrap();
+    let physics = PlayerPhysics::from_json(&json).unwrap();
+    assert_eq!(physics, PlayerPhysics::default());
+    let level = PhysicsOverride::from_json(r#"{ "move_factor": 4.5, "jump_down_curve": [1.0, 2.0, 4.0] }"#).unwrap();
+    let changed = physics.with(&level).unwrap();
+    assert_eq!(changed.move_factor, 4.5);
+    assert_eq!(changed.jump_down_curve, vec![1.0, 2.0, 4.0]);
+    assert_eq!(changed.jump_up_curve, physics.jump_up_curve);
+    let broken = PhysicsOverride::from_json(r#"{ "move_speed_curve": [] }"#).unwrap();
+    assert!(physics.with(&broken).is_err());
+}
+
+#[test]
+fn physics_with_values_the_player_can_not_move_with_are_rejected() {
+    let physics = PlayerPhysics::default();
+    let broken = [
+        PhysicsOverride { move_factor: Some(-1.0), ..Default::default() },
+        PhysicsOverride { jump_up_factor: Some(f32::NAN), ..Default::default() },
+        PhysicsOverride { jump_down_factor: Some(f32::INFINITY), ..Default::default() },
+        PhysicsOverride { air_control_divisor: Some(f32::NAN), ..Default::default() },
+        PhysicsOverride { jump_down_curve: Some(vec![1.0, f32::NAN, 2.0]), ..Default::default() },
+        PhysicsOverride { move_speed_curve: Some(vec![1.0, -2.0, 3.0]), ..Default::default() },
+    ];
+    for level in broken.iter() {
+        assert!(physics.with(level).is_err(), "accepted {:?}", level);
+    }
+    let json = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/physics.json")).unwrap();
+    let backwards = json.replace("\"move_factor\": 3.0", "\"move_factor\": -3.0");
+    assert_ne!(backwards, json);
+    assert!(PlayerPhysics::from_json(&backwards).is_err());
+}
+
+#[test]
+fn levels_with_an_unreadable_physics_override_keep_the_defaults() {
+    let mut source = tiled_source(HIDDEN_PLATFORM_MAP);
+    source.physics = br#"{ "move_factor": "fast" "#.to_vec();
+    let level = Level::with_textures(&source, vec![], TILED_CLIP).unwrap();
+    assert_eq!(level.physics, PhysicsOverride::default());
+}
+
+#[test]
+fn dropping_through_a_platform_does_not_keep_a_late_jump() {
+    // a one-way platform below the spawn, high above the floor
+    let source = tiled_source(r#"{
+        "width": 4, "height": 10, "tilewidth": 8, "tileheight": 8,
+        "tilesets": [{ "firstgid": 1, "columns": 16, "tilecount": 128 }],
+        "layers": [
+            { "name": "logic", "type": "tilelayer", "data": [
+                0, 3, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0,
+                0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0
+            ] },
+            { "name": "collision", "type": "tilelayer", "data": [
+                0, 0, 0, 0,  0, 0, 0, 0,  100, 100, 100, 100,  0, 0, 0, 0,  0, 0, 0, 0,
+                0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0,  1, 1, 1, 1
+            ] }
+        ]
+    }"#);
+    let (mut tilemap, layers, _) = load_tilemap(&source, TILED_CLIP).unwrap();
+    let properties = tiles_with(r#"[{ "id": 99, "one_way": true }]"#);
+    let mut body = spawn(&mut tilemap, &layers, &properties);
+    // long enough windows to still be open once the drop is over
+    body.set_physics(PlayerPhysics {
+        coyote_ticks: 20,
+        jump_buffer_ticks: 20,
+        ..PlayerPhysics::default()
+    });
+    let idle = PlayerInput::default();
+    // the drop must not turn into a jump once the platform is passed, neither from the ledge nor from the press
+    let drop = [(1, idle), (1, PlayerInput { duck: true, jump: true, ..idle }), (20, idle)];
+    let (_, mut events) = run_script(&mut body, &mut tilemap, &layers, &properties, &drop, 22);
+    assert!(body.position().y > 8.0, "did not drop through the platform");
+    events.extend(run_script(&mut body, &mut tilemap, &layers, &properties, &[(TICK_RATE, idle)], TICK_RATE).1);
+    assert_eq!(body.position().y, 56.0, "did not land on the floor");
+    assert!(!events.contains(&PlayerEvent::Jumped), "jumped after dropping: {:?}", events);
+}